@@ -0,0 +1,118 @@
+use std::ops::Range;
+
+use glam::DVec3;
+
+use crate::ray::Ray;
+
+/// Axis-aligned bounding box used by the BVH to skip
+/// whole groups of objects a ray can't reach.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: DVec3,
+    pub max: DVec3,
+}
+
+impl Aabb {
+    /// A box that contains nothing. It is the identity for `union`.
+    pub const EMPTY: Aabb = Aabb {
+        min: DVec3::splat(f64::INFINITY),
+        max: DVec3::splat(f64::NEG_INFINITY),
+    };
+
+    /// Builds the box spanned by two corner points, in any order.
+    pub fn new(a: DVec3, b: DVec3) -> Self {
+        Aabb {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// False for boxes of unbounded shapes such as planes.
+    pub fn is_finite(&self) -> bool {
+        self.min.is_finite() && self.max.is_finite()
+    }
+
+    /// Grows any side thinner than `delta` so flat shapes,
+    /// like an axis-aligned triangle, still have a volume the
+    /// slab test can hit.
+    pub fn pad(&self, delta: f64) -> Aabb {
+        let extent = self.max - self.min;
+        let grow = DVec3::select(
+            extent.cmplt(DVec3::splat(delta)),
+            DVec3::splat(delta / 2.),
+            DVec3::ZERO,
+        );
+        Aabb {
+            min: self.min - grow,
+            max: self.max + grow,
+        }
+    }
+
+    pub fn centroid(&self) -> DVec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let extent = (self.max - self.min).max(DVec3::ZERO);
+        2. * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
+    /// Index of the axis (0 = x, 1 = y, 2 = z) along which
+    /// the box is widest.
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.max - self.min;
+        if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Slab test. Returns true if the ray passes through the box
+    /// anywhere inside `interval`.
+    pub fn hit(&self, ray: &Ray, interval: Range<f64>) -> bool {
+        self.clip(ray, interval).is_some()
+    }
+
+    /// The part of `interval` the ray spends inside the box,
+    /// if any.
+    pub fn clip(
+        &self,
+        ray: &Ray,
+        interval: Range<f64>,
+    ) -> Option<Range<f64>> {
+        let mut t_min = interval.start;
+        let mut t_max = interval.end;
+
+        for axis in 0..3 {
+            let inv_d = ray.direction[axis].recip();
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_d;
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_d;
+            if inv_d < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            // written so that a NaN (ray origin lying on a slab
+            // plane it runs parallel to) keeps the old bound
+            if t0 > t_min {
+                t_min = t0;
+            }
+            if t1 < t_max {
+                t_max = t1;
+            }
+            if t_max <= t_min {
+                return None;
+            }
+        }
+        Some(t_min..t_max)
+    }
+}
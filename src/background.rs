@@ -0,0 +1,70 @@
+use glam::DVec3;
+
+pub mod environment;
+pub mod sky;
+
+use environment::EnvironmentMap;
+use sky::Sky;
+
+/// What a ray sees when it leaves the scene without hitting
+/// anything.
+pub enum Background {
+    Solid(DVec3),
+    /// Vertical blend from `bottom` (looking straight down)
+    /// to `top` (looking straight up)
+    Gradient {
+        bottom: DVec3,
+        top: DVec3,
+    },
+    Black,
+    /// Latitude/longitude image wrapped around the scene,
+    /// importance sampled as a light source
+    EnvironmentMap(EnvironmentMap),
+    /// Physically based daytime sky, see `Sky`
+    Sky(Sky),
+}
+
+impl Default for Background {
+    /// The white-to-blue daytime sky.
+    fn default() -> Self {
+        Background::Gradient {
+            bottom: DVec3::new(1.0, 1.0, 1.0),
+            top: DVec3::new(0.5, 0.7, 1.0),
+        }
+    }
+}
+
+impl Background {
+    /// Radiance arriving from `direction`.
+    pub fn color(&self, direction: DVec3) -> DVec3 {
+        match self {
+            Background::Solid(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let unit_direction = direction.normalize();
+                let a = 0.5 * (unit_direction.y + 1.0);
+                (1.0 - a) * *bottom + a * *top
+            }
+            Background::Black => DVec3::ZERO,
+            Background::EnvironmentMap(map) => map.color(direction),
+            Background::Sky(sky) => sky.color(direction),
+        }
+    }
+
+    /// Direction towards the background worth sending a shadow
+    /// ray along, with its solid angle density. `None` when the
+    /// background is not sampled as a light.
+    pub fn sample(&self) -> Option<(DVec3, f64)> {
+        match self {
+            Background::EnvironmentMap(map) => map.sample(),
+            _ => None,
+        }
+    }
+
+    /// Density with which `sample` returns `direction`.
+    pub fn pdf(&self, direction: DVec3) -> f64 {
+        match self {
+            Background::EnvironmentMap(map) => map.pdf(direction),
+            _ => 0.,
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::{f64::consts::PI, io, path::Path};
+
+use glam::{DMat3, DVec3};
+use rand::Rng;
+
+use crate::{
+    image::{invalid_data, Image},
+    spectrum::luminance,
+};
+
+/// Equirectangular image used as light coming from every
+/// direction, e.g. a Radiance `.hdr` capture of a studio.
+///
+/// Keeps a 2D luminance distribution over the pixels so
+/// directions towards bright spots such as the sun are sampled
+/// more often than dim sky.
+pub struct EnvironmentMap {
+    image: Image,
+    /// world from map rotation, about +y
+    rotation: DMat3,
+    /// cumulative weight of each row, normalized to end at 1
+    marginal_cdf: Vec<f64>,
+    /// cumulative weight along each row, normalized per row
+    conditional_cdf: Vec<Vec<f64>>,
+    /// probability of picking each pixel
+    pixel_probability: Vec<f64>,
+}
+
+impl EnvironmentMap {
+    /// `rotation` turns the map about the vertical axis, in radians.
+    /// Fails for an image without pixels.
+    pub fn new(image: Image, rotation: f64) -> io::Result<Self> {
+        let (width, height) = (image.width, image.height);
+        if width == 0 || height == 0 {
+            return Err(invalid_data("environment map has no pixels"));
+        }
+
+        // Rows near the poles cover less solid angle, so their
+        // pixels are weighted down by sin(theta)
+        let weights: Vec<f64> = (0..height)
+            .flat_map(|y| {
+                let sin_theta =
+                    (PI * (y as f64 + 0.5) / height as f64).sin();
+                let image = &image;
+                (0..width).map(move |x| {
+                    luminance(image.pixel(x, y)) * sin_theta
+                })
+            })
+            .collect();
+        let total: f64 = weights.iter().sum();
+
+        let mut conditional_cdf = Vec::with_capacity(height);
+        let mut row_weights = Vec::with_capacity(height);
+        for row in weights.chunks_exact(width) {
+            let row_total: f64 = row.iter().sum();
+            row_weights.push(row_total);
+            conditional_cdf.push(cumulative(row, row_total));
+        }
+        let marginal_cdf = cumulative(&row_weights, total);
+        let pixel_probability = weights
+            .iter()
+            .map(|weight| if total > 0. { weight / total } else { 0. })
+            .collect();
+
+        Ok(EnvironmentMap {
+            image,
+            rotation: DMat3::from_rotation_y(rotation),
+            marginal_cdf,
+            conditional_cdf,
+            pixel_probability,
+        })
+    }
+
+    /// Loads a `.hdr`, `.pfm` or `.ppm` file, see `Image::load`.
+    pub fn load(
+        path: impl AsRef<Path>,
+        rotation: f64,
+    ) -> io::Result<Self> {
+        Self::new(Image::load(path)?, rotation)
+    }
+
+    /// Radiance arriving from `direction`.
+    pub fn color(&self, direction: DVec3) -> DVec3 {
+        let (u, v) =
+            equirectangular_uv(self.rotation.transpose() * direction);
+        self.image.sample(u, v)
+    }
+
+    /// Random unit direction, more likely towards bright parts
+    /// of the map, and its density per unit solid angle.
+    /// `None` for an all-black map.
+    pub fn sample(&self) -> Option<(DVec3, f64)> {
+        let mut rng = rand::thread_rng();
+        let y = find_interval(&self.marginal_cdf, rng.gen())?;
+        let x = find_interval(&self.conditional_cdf[y], rng.gen())?;
+
+        // uniform position inside the chosen pixel
+        let u = (x as f64 + rng.gen::<f64>()) / self.image.width as f64;
+        let theta = PI * (y as f64 + rng.gen::<f64>())
+            / self.image.height as f64;
+        let phi = (u - 0.5) * 2. * PI;
+        let local = DVec3::new(
+            theta.sin() * phi.sin(),
+            theta.cos(),
+            -theta.sin() * phi.cos(),
+        );
+
+        let direction = self.rotation * local;
+        let pdf = self.pdf(direction);
+        (pdf > 0.).then_some((direction, pdf))
+    }
+
+    /// Density with which `sample` returns `direction`.
+    pub fn pdf(&self, direction: DVec3) -> f64 {
+        let local = (self.rotation.transpose() * direction).normalize();
+        let theta = local.y.clamp(-1., 1.).acos();
+        let sin_theta = theta.sin();
+        if sin_theta <= 0. {
+            return 0.;
+        }
+
+        let (u, _) = equirectangular_uv(local);
+        let (width, height) = (self.image.width, self.image.height);
+        let x = ((u * width as f64) as usize).min(width - 1);
+        let y = ((theta / PI * height as f64) as usize).min(height - 1);
+
+        // density over the [0, 1]² map, converted to solid angle
+        let map_pdf = self.pixel_probability[y * width + x]
+            * (width * height) as f64;
+        map_pdf / (2. * PI * PI * sin_theta)
+    }
+}
+
+/// Maps a direction to equirectangular texture coordinates:
+/// `u` runs once around the horizon with -z in the middle, `v`
+/// from straight down (0) to straight up (1).
+fn equirectangular_uv(direction: DVec3) -> (f64, f64) {
+    let d = direction.normalize();
+    let phi = d.x.atan2(-d.z);
+    let theta = d.y.clamp(-1., 1.).acos();
+    (0.5 + phi / (2. * PI), 1. - theta / PI)
+}
+
+/// Running sum of `weights` divided by `total`, one entry per weight
+fn cumulative(weights: &[f64], total: f64) -> Vec<f64> {
+    let mut sum = 0.;
+    weights
+        .iter()
+        .map(|weight| {
+            sum += weight;
+            if total > 0. {
+                sum / total
+            } else {
+                0.
+            }
+        })
+        .collect()
+}
+
+/// Index of the first entry of `cdf` above `value`, skipping
+/// entries with zero probability
+fn find_interval(cdf: &[f64], value: f64) -> Option<usize> {
+    if cdf.last().is_none_or(|last| *last <= 0.) {
+        return None;
+    }
+    let index = cdf.partition_point(|c| *c <= value);
+    Some(index.min(cdf.len() - 1))
+}
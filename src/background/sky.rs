@@ -0,0 +1,204 @@
+use std::f64::consts::PI;
+
+use glam::DVec3;
+
+use crate::{
+    lights::directional::DirectionalLight, spectrum::xyz_to_rgb,
+};
+
+/// Converts the model's luminance in kcd/m² to the units the
+/// renderer works in; puts a clear midday zenith around 0.5,
+/// close to the default gradient.
+const LUMINANCE_SCALE: f64 = 0.05;
+
+/// Illuminance of the sun above the atmosphere, in klux.
+const SOLAR_ILLUMINANCE: f64 = 127.5;
+
+/// Clear daytime sky after Preetham, Shirley and Smits, "A
+/// Practical Analytic Model for Daylight" (1999).
+///
+/// The sun disc itself is not part of the background; add
+/// `Sky::sun` to the scene's lights so it casts sharp shadows:
+///
+/// ```ignore
+/// let sky = Sky::new(35f64.to_radians(), 0.5, 3.);
+/// let scene = Scene::new(world)
+///     .with_light(sky.sun())
+///     .with_background(Background::Sky(sky));
+/// ```
+pub struct Sky {
+    /// Unit vector pointing at the sun
+    sun_direction: DVec3,
+    turbidity: f64,
+    /// Perez coefficients for luminance and the two
+    /// chromaticity coordinates
+    perez_luminance: Perez,
+    perez_x: Perez,
+    perez_y: Perez,
+    /// Value of luminance, x and y straight up
+    zenith: DVec3,
+}
+
+impl Sky {
+    /// `elevation` is the sun's angle above the horizon and
+    /// `azimuth` its angle around the vertical axis from -z
+    /// towards +x, both in radians. `turbidity` describes the
+    /// haze, from 2 (very clear) to about 10 (hazy).
+    pub fn new(elevation: f64, azimuth: f64, turbidity: f64) -> Self {
+        let sun_direction = DVec3::new(
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+            -elevation.cos() * azimuth.cos(),
+        );
+        let t = turbidity;
+        let theta_sun = PI / 2. - elevation.clamp(0., PI / 2.);
+
+        let chi = (4. / 9. - t / 120.) * (PI - 2. * theta_sun);
+        let zenith_luminance =
+            (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let zenith_x = zenith_chromaticity(
+            t,
+            theta_sun,
+            [
+                [0.00166, -0.00375, 0.00209, 0.],
+                [-0.02903, 0.06377, -0.03202, 0.00394],
+                [0.11693, -0.21196, 0.06052, 0.25886],
+            ],
+        );
+        let zenith_y = zenith_chromaticity(
+            t,
+            theta_sun,
+            [
+                [0.00275, -0.00610, 0.00317, 0.],
+                [-0.04214, 0.08970, -0.04153, 0.00516],
+                [0.15346, -0.26756, 0.06670, 0.26688],
+            ],
+        );
+
+        Sky {
+            sun_direction,
+            turbidity,
+            perez_luminance: Perez {
+                a: 0.1787 * t - 1.4630,
+                b: -0.3554 * t + 0.4275,
+                c: -0.0227 * t + 5.3251,
+                d: 0.1206 * t - 2.5771,
+                e: -0.0670 * t + 0.3703,
+            },
+            perez_x: Perez {
+                a: -0.0193 * t - 0.2592,
+                b: -0.0665 * t + 0.0008,
+                c: -0.0004 * t + 0.2125,
+                d: -0.0641 * t - 0.8989,
+                e: -0.0033 * t + 0.0452,
+            },
+            perez_y: Perez {
+                a: -0.0167 * t - 0.2608,
+                b: -0.0950 * t + 0.0092,
+                c: -0.0079 * t + 0.2102,
+                d: -0.0441 * t - 1.6537,
+                e: -0.0109 * t + 0.0529,
+            },
+            zenith: DVec3::new(zenith_luminance, zenith_x, zenith_y),
+        }
+    }
+
+    /// Unit vector pointing at the sun.
+    pub fn sun_direction(&self) -> DVec3 {
+        self.sun_direction
+    }
+
+    /// Radiance arriving from `direction`. The model only
+    /// covers the upper hemisphere, directions below the
+    /// horizon see the sky just above it.
+    pub fn color(&self, direction: DVec3) -> DVec3 {
+        let mut direction = direction.normalize();
+        direction.y = direction.y.max(0.01);
+        let direction = direction.normalize();
+
+        let cos_theta = direction.y;
+        let gamma =
+            direction.dot(self.sun_direction).clamp(-1., 1.).acos();
+        let theta_sun = self.sun_direction.y.clamp(0., 1.).acos();
+
+        // each quantity is its zenith value scaled by the
+        // Perez distribution relative to the zenith
+        let relative = |perez: &Perez| {
+            perez.value(cos_theta, gamma) / perez.value(1., theta_sun)
+        };
+        let luminance = self.zenith.x * relative(&self.perez_luminance);
+        let x = self.zenith.y * relative(&self.perez_x);
+        let y = self.zenith.z * relative(&self.perez_y);
+
+        let xyz = DVec3::new(x / y, 1., (1. - x - y) / y) * luminance;
+        (xyz_to_rgb(xyz) * LUMINANCE_SCALE).max(DVec3::ZERO)
+    }
+
+    /// Directional light for the sun, tinted by the
+    /// atmosphere it passes through.
+    pub fn sun(&self) -> DirectionalLight {
+        DirectionalLight {
+            direction: -self.sun_direction,
+            color: self.sun_transmittance(),
+            intensity: SOLAR_ILLUMINANCE * LUMINANCE_SCALE,
+        }
+    }
+
+    /// Fraction of sunlight reaching the ground for red, green
+    /// and blue, from Rayleigh and aerosol scattering along
+    /// the sun's path through the air.
+    fn sun_transmittance(&self) -> DVec3 {
+        if self.sun_direction.y <= 0. {
+            return DVec3::ZERO;
+        }
+        let theta_sun = self.sun_direction.y.acos().to_degrees();
+        // relative optical air mass, Kasten and Young
+        let air_mass = 1.
+            / (self.sun_direction.y
+                + 0.50572 * (96.07995 - theta_sun).powf(-1.6364));
+
+        // representative wavelengths in micrometers
+        let wavelengths = DVec3::new(0.680, 0.550, 0.440);
+        let rayleigh = 0.008735 * wavelengths.powf(-4.08);
+        let beta = 0.04608 * self.turbidity - 0.04586;
+        let aerosol = beta * wavelengths.powf(-1.3);
+
+        DVec3::exp(-air_mass * (rayleigh + aerosol))
+    }
+}
+
+/// Coefficients of the Perez all-weather sky distribution
+struct Perez {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+}
+
+impl Perez {
+    /// Distribution at zenith angle `theta` (given by its
+    /// cosine) and angle `gamma` away from the sun
+    fn value(&self, cos_theta: f64, gamma: f64) -> f64 {
+        (1. + self.a * (self.b / cos_theta).exp())
+            * (1.
+                + self.c * (self.d * gamma).exp()
+                + self.e * gamma.cos().powi(2))
+    }
+}
+
+/// Chromaticity straight up, a cubic in the sun's zenith angle
+/// for each power of the turbidity
+fn zenith_chromaticity(
+    turbidity: f64,
+    theta_sun: f64,
+    coefficients: [[f64; 4]; 3],
+) -> f64 {
+    let powers = [theta_sun.powi(3), theta_sun.powi(2), theta_sun, 1.];
+    let cubic = |row: [f64; 4]| -> f64 {
+        row.iter().zip(powers).map(|(c, p)| c * p).sum()
+    };
+    turbidity * turbidity * cubic(coefficients[0])
+        + turbidity * cubic(coefficients[1])
+        + cubic(coefficients[2])
+}
@@ -1,4 +1,6 @@
-use crate::{hittable::Hittable, ray::Ray};
+use crate::{
+    hittable::Hittable, ray::Ray, scene::Scene, spectrum::Wavelengths,
+};
 use glam::DVec3;
 use indicatif::ParallelProgressIterator;
 use itertools::Itertools;
@@ -57,6 +59,8 @@ pub struct Camera {
     defocus_disk_u: DVec3,
     /// Defocus disk vertical radius
     defocus_disk_v: DVec3,
+    /// Whether camera rays carry wavelengths
+    spectral: bool,
 }
 
 impl Default for Camera {
@@ -89,6 +93,7 @@ impl Camera {
         Ray {
             origin: self.center,
             direction: ray_direction,
+            wavelengths: self.spectral.then(Wavelengths::sample),
         }
     }
     fn defocus_disk_sample(&self) -> DVec3 {
@@ -111,6 +116,16 @@ impl Camera {
         &self,
         world: T,
     ) -> io::Result<()>
+    where
+        T: Hittable + std::marker::Sync,
+    {
+        self.render_scene_to_disk(&Scene::new(world))
+    }
+    /// Like `render_to_disk`, for a scene with lights.
+    pub fn render_scene_to_disk<T>(
+        &self,
+        scene: &Scene<T>,
+    ) -> io::Result<()>
     where
         T: Hittable + std::marker::Sync,
     {
@@ -133,7 +148,7 @@ impl Camera {
                         self.get_ray(x as i32, y as i32)
                             .color(
                                 self.max_depth as i32,
-                                &world,
+                                scene,
                             )
                     })
                     .sum::<DVec3>()
@@ -12,6 +12,7 @@ pub struct CameraBuilder {
     defocus_angle: f64,
     samples_per_pixel: u32,
     max_depth: u32,
+    spectral: bool,
 }
 impl Default for CameraBuilder {
     fn default() -> Self {
@@ -25,6 +26,7 @@ impl Default for CameraBuilder {
             defocus_angle: 0.,
             samples_per_pixel: 100,
             max_depth: 50,
+            spectral: false,
         }
     }
 }
@@ -90,6 +92,13 @@ impl CameraBuilder {
         self.max_depth = max_depth;
         self
     }
+    /// Traces wavelengths instead of RGB, so dispersive glass
+    /// splits light into colors. Slower to converge, so off by
+    /// default.
+    pub fn spectral(mut self, spectral: bool) -> CameraBuilder {
+        self.spectral = spectral;
+        self
+    }
     pub fn build(self) -> Camera {
         let max_value: u8 = 255;
         let image_height: u32 = (self.image_width as f64
@@ -164,6 +173,7 @@ impl CameraBuilder {
             focus_dist: self.focus_dist,
             defocus_disk_u,
             defocus_disk_v,
+            spectral: self.spectral,
         }
     }
 }
\ No newline at end of file
@@ -1,18 +1,55 @@
-use crate::{ material::Material, ray::Ray };
+use crate::{ aabb::Aabb, material::Material, ray::Ray };
 use glam::DVec3;
-use std::ops::Range;
+use rand::Rng;
+use std::{ ops::Range, sync::Arc };
 
-pub trait Hittable: Sync {
+pub mod bvh;
+pub mod constant_medium;
+pub mod grid_medium;
+pub mod normal_mapped;
+pub mod transformed;
+
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord>;
+    /// World-space box that fully encloses the object.
+    fn bounding_box(&self) -> Aabb;
+
+    /// Probability density, per unit solid angle, that `random`
+    /// picks `direction` when called from `origin`. Emissive
+    /// shapes implement this pair so the renderer can aim rays at
+    /// them; the defaults mean "can't be sampled".
+    fn pdf_value(&self, _origin: DVec3, _direction: DVec3) -> f64 {
+        0.0
+    }
+    /// Random direction from `origin` towards a point on the object.
+    fn random(&self, _origin: DVec3) -> DVec3 {
+        DVec3::X
+    }
+    /// Fraction of light that makes it along the ray through
+    /// `interval`, for shadow rays. Surfaces block all of it;
+    /// participating media override this to let some through.
+    fn transmittance(&self, ray: &Ray, interval: Range<f64>) -> f64 {
+        if self.hit(ray, interval).is_some() { 0.0 } else { 1.0 }
+    }
 }
 
 #[derive(Clone)]
 pub struct HitRecord {
     pub point: DVec3,
     pub normal: DVec3,
-    t: f64,
+    /// Ray parameter of the hit; the distance travelled to it
+    /// is `t` times the length of the ray's direction.
+    pub t: f64,
     pub front_face: bool,
     pub material: Material,
+    /// Texture coordinates of the hit, both usually in `[0, 1]`
+    pub u: f64,
+    pub v: f64,
+    /// Unit vectors along the surface in the directions `u`
+    /// and `v` grow. They follow the surface, so unlike
+    /// `normal` they don't flip on back faces.
+    pub tangent: DVec3,
+    pub bitangent: DVec3,
 }
 impl HitRecord {
     pub fn with_face_normal(
@@ -23,14 +60,39 @@ impl HitRecord {
         ray: &Ray
     ) -> Self {
         let (front_face, normal) = HitRecord::calc_face_normal(ray, &outward_normal);
+        // some frame is better than none for shapes without a
+        // parameterization
+        let (tangent, bitangent) = outward_normal.any_orthonormal_pair();
         HitRecord {
             material,
             point,
             normal,
             t,
             front_face,
+            u: 0.0,
+            v: 0.0,
+            tangent,
+            bitangent,
         }
     }
+    /// Sets the texture coordinates of the hit.
+    pub fn with_uv(mut self, u: f64, v: f64) -> Self {
+        self.u = u;
+        self.v = v;
+        self
+    }
+    /// Sets the surface directions along which `u` and `v`
+    /// grow; they are normalized here. A zero vector, as at the
+    /// poles of a sphere, keeps the default frame.
+    pub fn with_tangents(mut self, tangent: DVec3, bitangent: DVec3) -> Self {
+        if let (Some(tangent), Some(bitangent)) =
+            (tangent.try_normalize(), bitangent.try_normalize())
+        {
+            self.tangent = tangent;
+            self.bitangent = bitangent;
+        }
+        self
+    }
     fn calc_face_normal(ray: &Ray, outward_normal: &DVec3) -> (bool, DVec3) {
         // TODO: Why is outward_normal.is_normalized() false
         // for some normals for which these two values are exactly the same:
@@ -61,6 +123,39 @@ impl<T> Hittable for Vec<T> where T: Hittable + Sync {
 
         hit_record
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.iter().fold(Aabb::EMPTY, |acc, item| acc.union(&item.bounding_box()))
+    }
+
+    // Sampling a list picks one of its items at random, so the
+    // density is the average of theirs
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self.iter().map(|item| item.pdf_value(origin, direction)).sum();
+        sum / (self.len() as f64)
+    }
+
+    fn random(&self, origin: DVec3) -> DVec3 {
+        if self.is_empty() {
+            return DVec3::X;
+        }
+        let index = rand::thread_rng().gen_range(0..self.len());
+        self[index].random(origin)
+    }
+
+    fn transmittance(&self, ray: &Ray, interval: Range<f64>) -> f64 {
+        let mut transmittance = 1.0;
+        for item in self {
+            transmittance *= item.transmittance(ray, interval.clone());
+            if transmittance == 0.0 {
+                break;
+            }
+        }
+        transmittance
+    }
 }
 
 impl Hittable for Box<dyn Hittable> {
@@ -68,4 +163,43 @@ impl Hittable for Box<dyn Hittable> {
         // Delegate to the contained object's hit method
         self.as_ref().hit(ray, interval)
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.as_ref().bounding_box()
+    }
+
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        self.as_ref().pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: DVec3) -> DVec3 {
+        self.as_ref().random(origin)
+    }
+
+    fn transmittance(&self, ray: &Ray, interval: Range<f64>) -> f64 {
+        self.as_ref().transmittance(ray, interval)
+    }
+}
+
+// Lets one object be in the world and in the scene's emitter list
+impl<T> Hittable for Arc<T> where T: Hittable + ?Sized {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+        self.as_ref().hit(ray, interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.as_ref().bounding_box()
+    }
+
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        self.as_ref().pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: DVec3) -> DVec3 {
+        self.as_ref().random(origin)
+    }
+
+    fn transmittance(&self, ray: &Ray, interval: Range<f64>) -> f64 {
+        self.as_ref().transmittance(ray, interval)
+    }
 }
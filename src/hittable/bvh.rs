@@ -0,0 +1,147 @@
+use std::ops::Range;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    ray::Ray,
+};
+
+mod builder;
+pub use builder::BvhBuilder;
+
+/// Relative cost of visiting an interior node versus testing
+/// a primitive, used by the surface area heuristic
+const TRAVERSAL_COST: f64 = 1.;
+const INTERSECTION_COST: f64 = 1.;
+
+/// Bounding volume hierarchy over a list of hittables.
+///
+/// Drop-in replacement for a `Vec<Box<dyn Hittable>>` world:
+/// instead of testing every object, a ray only descends into
+/// the nodes whose bounding boxes it actually crosses.
+pub struct BvhNode {
+    bbox: Aabb,
+    node: Node,
+}
+
+enum Node {
+    Leaf(Vec<Box<dyn Hittable>>),
+    Branch {
+        left: Box<BvhNode>,
+        right: Box<BvhNode>,
+    },
+}
+
+/// Shape of a built tree, see `BvhNode::stats`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BvhStats {
+    /// Interior and leaf nodes together
+    pub node_count: usize,
+    pub leaf_count: usize,
+    /// Longest root-to-leaf path; a single leaf has depth 1
+    pub depth: usize,
+    /// Most primitives stored in any one leaf
+    pub max_leaf_primitives: usize,
+    /// Surface area heuristic cost of the whole tree, i.e. the
+    /// expected number of node visits plus primitive tests for
+    /// a random ray that hits the root box
+    pub sah_cost: f64,
+}
+
+impl BvhNode {
+    /// Builds the tree with the default `BvhBuilder` settings.
+    ///
+    /// Panics if `objects` is empty.
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Self {
+        Self::init().build(objects)
+    }
+
+    pub fn init() -> BvhBuilder {
+        BvhBuilder::default()
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats {
+            node_count: 0,
+            leaf_count: 0,
+            depth: 0,
+            max_leaf_primitives: 0,
+            sah_cost: 0.,
+        };
+        // with planes in the scene the root box is infinite, so
+        // measure against the bounded part of the tree instead
+        let root_area = match &self.node {
+            Node::Branch { left, .. } if !self.bbox.is_finite() => left.bbox.surface_area(),
+            _ => self.bbox.surface_area(),
+        };
+        self.collect_stats(&mut stats, 1, root_area);
+        stats
+    }
+
+    fn collect_stats(&self, stats: &mut BvhStats, depth: usize, root_area: f64) {
+        // probability that a ray through the root box also
+        // passes through this one
+        let area_ratio = if self.bbox.is_finite() && root_area.is_finite() && root_area > 0. {
+            self.bbox.surface_area() / root_area
+        } else {
+            1.
+        };
+
+        stats.node_count += 1;
+        stats.depth = stats.depth.max(depth);
+        match &self.node {
+            Node::Leaf(objects) => {
+                stats.leaf_count += 1;
+                stats.max_leaf_primitives = stats.max_leaf_primitives.max(objects.len());
+                stats.sah_cost += area_ratio * objects.len() as f64 * INTERSECTION_COST;
+            }
+            Node::Branch { left, right } => {
+                stats.sah_cost += area_ratio * TRAVERSAL_COST;
+                left.collect_stats(stats, depth + 1, root_area);
+                right.collect_stats(stats, depth + 1, root_area);
+            }
+        }
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+        if !self.bbox.hit(ray, interval.clone()) {
+            return None;
+        }
+
+        match &self.node {
+            Node::Leaf(objects) => objects.hit(ray, interval),
+            Node::Branch { left, right } => {
+                let hit_left = left.hit(ray, interval.clone());
+                // anything behind the left hit is occluded,
+                // so the right side only has to beat it
+                let end = hit_left.as_ref().map_or(interval.end, |rec| rec.t);
+                let hit_right = right.hit(ray, interval.start..end);
+
+                hit_right.or(hit_left)
+            }
+        }
+    }
+
+    fn transmittance(&self, ray: &Ray, interval: Range<f64>) -> f64 {
+        if !self.bbox.hit(ray, interval.clone()) {
+            return 1.0;
+        }
+
+        match &self.node {
+            Node::Leaf(objects) => objects.transmittance(ray, interval),
+            Node::Branch { left, right } => {
+                let left = left.transmittance(ray, interval.clone());
+                if left == 0.0 {
+                    return 0.0;
+                }
+                left * right.transmittance(ray, interval)
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+}
@@ -0,0 +1,223 @@
+use glam::DVec3;
+
+use super::{BvhNode, Node, INTERSECTION_COST, TRAVERSAL_COST};
+use crate::{aabb::Aabb, hittable::Hittable};
+
+/// Builds a `BvhNode` using binned surface area heuristic splits.
+///
+/// Every candidate split is scored by the expected cost of
+/// tracing a ray through the two children; a node becomes a
+/// leaf once no split beats testing its primitives directly
+/// and it holds no more than `max_leaf_size` of them.
+pub struct BvhBuilder {
+    max_leaf_size: usize,
+    bin_count: usize,
+    parallel_threshold: usize,
+}
+impl Default for BvhBuilder {
+    fn default() -> Self {
+        Self {
+            max_leaf_size: 4,
+            bin_count: 16,
+            parallel_threshold: 1024,
+        }
+    }
+}
+
+/// An object together with its cached bounds, so the boxes
+/// don't have to be recomputed at every level of the build
+struct Primitive {
+    bbox: Aabb,
+    centroid: DVec3,
+    object: Box<dyn Hittable>,
+}
+
+struct Split {
+    axis: usize,
+    /// last bin that goes to the left child
+    bin: usize,
+    cost: f64,
+}
+
+impl BvhBuilder {
+    /// Upper bound on the number of primitives in a leaf.
+    /// Larger leaves mean a shallower, cheaper to build tree.
+    pub fn max_leaf_size(mut self, max_leaf_size: usize) -> BvhBuilder {
+        self.max_leaf_size = max_leaf_size.max(1);
+        self
+    }
+    /// Number of buckets per axis the centroids are sorted
+    /// into when evaluating split candidates.
+    pub fn bin_count(mut self, bin_count: usize) -> BvhBuilder {
+        self.bin_count = bin_count.max(2);
+        self
+    }
+    /// Subtrees with at least this many primitives are built
+    /// on the rayon thread pool.
+    pub fn parallel_threshold(mut self, parallel_threshold: usize) -> BvhBuilder {
+        self.parallel_threshold = parallel_threshold;
+        self
+    }
+
+    /// Objects with an unbounded box (planes) can't be placed
+    /// in the tree; they are kept in a list next to it and
+    /// tested against every ray.
+    ///
+    /// Panics if `objects` is empty.
+    pub fn build(self, objects: Vec<Box<dyn Hittable>>) -> BvhNode {
+        assert!(!objects.is_empty(), "cannot build a BVH from an empty list");
+
+        let (primitives, unbounded): (Vec<_>, Vec<_>) = objects
+            .into_iter()
+            .map(|object| {
+                let bbox = object.bounding_box();
+                Primitive {
+                    bbox,
+                    centroid: bbox.centroid(),
+                    object,
+                }
+            })
+            .partition(|p| p.bbox.is_finite());
+
+        if unbounded.is_empty() {
+            return self.build_node(primitives);
+        }
+        let unbounded = Self::leaf(
+            unbounded.iter().fold(Aabb::EMPTY, |acc, p| acc.union(&p.bbox)),
+            unbounded,
+        );
+        if primitives.is_empty() {
+            return unbounded;
+        }
+
+        let tree = self.build_node(primitives);
+        BvhNode {
+            bbox: tree.bbox.union(&unbounded.bbox),
+            node: Node::Branch {
+                left: Box::new(tree),
+                right: Box::new(unbounded),
+            },
+        }
+    }
+
+    fn build_node(&self, mut primitives: Vec<Primitive>) -> BvhNode {
+        let bbox = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |acc, p| acc.union(&p.bbox));
+        let count = primitives.len();
+        if count == 1 {
+            return Self::leaf(bbox, primitives);
+        }
+
+        let centroid_bounds = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |acc, p| acc.union(&Aabb::new(p.centroid, p.centroid)));
+        let leaf_cost = count as f64 * INTERSECTION_COST;
+        let must_split = count > self.max_leaf_size;
+
+        let right = match self.find_split(&primitives, &bbox, &centroid_bounds) {
+            Some(split) if must_split || split.cost < leaf_cost => {
+                let (left, right) = primitives.into_iter().partition(|p| {
+                    self.bin_index(p.centroid[split.axis], &centroid_bounds, split.axis)
+                        <= split.bin
+                });
+                primitives = left;
+                right
+            }
+            // every centroid sits in the same spot, so binning
+            // can't separate them. Halve the list instead.
+            None if must_split => primitives.split_off(count / 2),
+            _ => return Self::leaf(bbox, primitives),
+        };
+
+        let (left, right) = if count >= self.parallel_threshold {
+            rayon::join(
+                || self.build_node(primitives),
+                || self.build_node(right),
+            )
+        } else {
+            (self.build_node(primitives), self.build_node(right))
+        };
+
+        BvhNode {
+            bbox,
+            node: Node::Branch {
+                left: Box::new(left),
+                right: Box::new(right),
+            },
+        }
+    }
+
+    fn leaf(bbox: Aabb, primitives: Vec<Primitive>) -> BvhNode {
+        BvhNode {
+            bbox,
+            node: Node::Leaf(primitives.into_iter().map(|p| p.object).collect()),
+        }
+    }
+
+    fn bin_index(&self, value: f64, centroid_bounds: &Aabb, axis: usize) -> usize {
+        let min = centroid_bounds.min[axis];
+        let extent = centroid_bounds.max[axis] - min;
+        let bin = ((value - min) / extent * self.bin_count as f64) as usize;
+        bin.min(self.bin_count - 1)
+    }
+
+    /// Cheapest split over all axes, or `None` if the
+    /// centroids can't be told apart along any of them.
+    fn find_split(
+        &self,
+        primitives: &[Primitive],
+        bbox: &Aabb,
+        centroid_bounds: &Aabb,
+    ) -> Option<Split> {
+        let parent_area = bbox.surface_area().max(f64::MIN_POSITIVE);
+        let mut best: Option<Split> = None;
+
+        for axis in 0..3 {
+            let extent = centroid_bounds.max[axis] - centroid_bounds.min[axis];
+            if extent.is_nan() || extent <= 0. {
+                continue;
+            }
+
+            let mut bins = vec![(Aabb::EMPTY, 0usize); self.bin_count];
+            for p in primitives {
+                let bin = &mut bins[self.bin_index(p.centroid[axis], centroid_bounds, axis)];
+                bin.0 = bin.0.union(&p.bbox);
+                bin.1 += 1;
+            }
+
+            // sweep from the right so every split position
+            // knows the area and count of everything after it
+            let mut right = vec![(0., 0usize); self.bin_count];
+            let mut bounds = Aabb::EMPTY;
+            let mut count = 0;
+            for i in (1..self.bin_count).rev() {
+                bounds = bounds.union(&bins[i].0);
+                count += bins[i].1;
+                right[i] = (bounds.surface_area(), count);
+            }
+
+            let mut bounds = Aabb::EMPTY;
+            let mut count = 0;
+            for bin in 0..self.bin_count - 1 {
+                bounds = bounds.union(&bins[bin].0);
+                count += bins[bin].1;
+                let (right_area, right_count) = right[bin + 1];
+                if count == 0 || right_count == 0 {
+                    continue;
+                }
+
+                let cost = TRAVERSAL_COST
+                    + INTERSECTION_COST
+                        * (bounds.surface_area() * count as f64
+                            + right_area * right_count as f64)
+                        / parent_area;
+                if best.as_ref().is_none_or(|best| cost < best.cost) {
+                    best = Some(Split { axis, bin, cost });
+                }
+            }
+        }
+
+        best
+    }
+}
@@ -0,0 +1,125 @@
+use std::ops::Range;
+
+use rand::Rng;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    texture::Texture,
+};
+
+/// Fog, smoke or any other participating medium of uniform
+/// density filling a closed boundary shape.
+///
+/// Instead of stopping at the boundary, rays travel an
+/// exponentially distributed distance into the volume and
+/// scatter there, or pass through if they leave it first.
+/// Thin media look like haze, dense ones like a solid with
+/// light bleeding under its surface.
+pub struct ConstantMedium<H: Hittable> {
+    boundary: H,
+    /// Chance of scattering per unit distance travelled
+    density: f64,
+    phase_function: Material,
+}
+
+impl<H: Hittable> ConstantMedium<H> {
+    /// A medium that scatters equally in all directions,
+    /// keeping `albedo` of the light each time. `boundary`
+    /// must be closed, like a `Sphere` or a `Cuboid`.
+    pub fn new(
+        boundary: H,
+        density: f64,
+        albedo: impl Into<Texture>,
+    ) -> Self {
+        Self::with_phase_function(
+            boundary,
+            density,
+            Material::Isotropic {
+                albedo: albedo.into(),
+            },
+        )
+    }
+
+    /// Like `new`, with any material for the scattering.
+    pub fn with_phase_function(
+        boundary: H,
+        density: f64,
+        phase_function: Material,
+    ) -> Self {
+        ConstantMedium {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+
+    /// The part of `interval` the ray spends inside the
+    /// boundary
+    fn inside(
+        &self,
+        ray: &Ray,
+        interval: Range<f64>,
+    ) -> Option<Range<f64>> {
+        // where the ray line enters and leaves the boundary,
+        // even if that's behind the origin
+        let entry =
+            self.boundary.hit(ray, f64::NEG_INFINITY..f64::INFINITY)?;
+        let exit =
+            self.boundary.hit(ray, entry.t + 0.0001..f64::INFINITY)?;
+
+        let start = entry.t.max(interval.start);
+        let end = exit.t.min(interval.end);
+        (start < end).then_some(start..end)
+    }
+}
+
+impl<H: Hittable> Hittable for ConstantMedium<H> {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Range<f64>,
+    ) -> Option<HitRecord> {
+        let Range { start, end } = self.inside(ray, interval)?;
+        let speed = ray.direction.length();
+        let distance_inside = (end - start) * speed;
+        let hit_distance = free_flight(self.density);
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = start + hit_distance / speed;
+        // a volume has no surface; face the normal at the
+        // viewer so the scattering point counts as a front face
+        Some(HitRecord::with_face_normal(
+            self.phase_function.clone(),
+            ray.at(t),
+            -ray.direction.normalize(),
+            t,
+            ray,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    // uniform density attenuates exponentially with distance
+    fn transmittance(&self, ray: &Ray, interval: Range<f64>) -> f64 {
+        self.inside(ray, interval).map_or(1., |inside| {
+            let distance =
+                (inside.end - inside.start) * ray.direction.length();
+            (-self.density * distance).exp()
+        })
+    }
+}
+
+/// Distance a ray travels through a medium of `density` before
+/// it collides with a particle, exponentially distributed.
+pub(super) fn free_flight(density: f64) -> f64 {
+    // 1 - u keeps the log finite
+    let u = rand::thread_rng().gen::<f64>();
+    -(1. - u).ln() / density
+}
@@ -0,0 +1,185 @@
+use std::{io, ops::Range, path::Path, sync::Arc};
+
+use glam::DVec3;
+use rand::Rng;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{constant_medium::free_flight, HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    voxel_grid::VoxelGrid,
+};
+
+/// Smoke, clouds or fire whose density varies through space,
+/// read from a voxel grid. Place it with `Transformed`; like
+/// the rest of the grid, density is measured in the grid's own
+/// units, so scaling the volume keeps how see-through it is.
+///
+/// Collisions are found with delta tracking and shadow rays
+/// are attenuated with ratio tracking (see Novák et al.,
+/// "Monte Carlo Methods for Volumetric Light Transport
+/// Simulation", 2018). Both step through the volume as if it
+/// were filled with its densest voxel everywhere and treat the
+/// extra collisions as fictitious, so the result is unbiased
+/// without ever integrating the density.
+pub struct GridMedium {
+    density: Arc<VoxelGrid>,
+    /// Extinction per unit distance for a grid value of one
+    density_scale: f64,
+    /// Upper bound of the extinction anywhere in the grid
+    majorant: f64,
+    /// Fraction of the extinction that scatters rather than
+    /// absorbs
+    albedo: DVec3,
+    /// Henyey-Greenstein asymmetry, see
+    /// `Material::HenyeyGreenstein`
+    asymmetry: f64,
+    /// Grid of emission strengths and the color it scales
+    emission: Option<(Arc<VoxelGrid>, DVec3)>,
+}
+
+impl GridMedium {
+    /// A white, isotropically scattering medium with the
+    /// grid's values times `density_scale` as its extinction.
+    pub fn new(density: VoxelGrid, density_scale: f64) -> Self {
+        let majorant = density.max_value() * density_scale.max(0.);
+        GridMedium {
+            density: Arc::new(density),
+            density_scale: density_scale.max(0.),
+            majorant,
+            albedo: DVec3::ONE,
+            asymmetry: 0.,
+            emission: None,
+        }
+    }
+
+    /// Loads the density grid, see `VoxelGrid::load`.
+    pub fn load(
+        path: impl AsRef<Path>,
+        density_scale: f64,
+    ) -> io::Result<Self> {
+        Ok(Self::new(VoxelGrid::load(path)?, density_scale))
+    }
+
+    /// Sets the color of the scattered light; the rest is
+    /// absorbed.
+    pub fn with_albedo(mut self, albedo: DVec3) -> Self {
+        self.albedo = albedo.clamp(DVec3::ZERO, DVec3::ONE);
+        self
+    }
+
+    /// Sets the Henyey-Greenstein asymmetry: about 0.8 for
+    /// clouds, a little above 0 for smoke.
+    pub fn with_asymmetry(mut self, asymmetry: f64) -> Self {
+        self.asymmetry = asymmetry.clamp(-0.99, 0.99);
+        self
+    }
+
+    /// Makes the medium glow with `color` times the values of
+    /// `emission`, such as a fire simulation's temperature
+    /// mapped to brightness. Only the absorbing part of the
+    /// medium emits, so flames need some density where they
+    /// burn, and the default white albedo absorbs nothing:
+    /// set `with_albedo` below one, or nothing glows.
+    pub fn with_emission(
+        mut self,
+        emission: VoxelGrid,
+        color: DVec3,
+    ) -> Self {
+        self.emission = Some((Arc::new(emission), color));
+        self
+    }
+
+    fn extinction(&self, point: DVec3) -> f64 {
+        self.density_scale * self.density.sample(point).max(0.)
+    }
+
+    /// Steps to the next tentative collision with the majorant,
+    /// or `None` once past `end`
+    fn next_collision(
+        &self,
+        ray: &Ray,
+        t: f64,
+        end: f64,
+    ) -> Option<f64> {
+        let speed = ray.direction.length();
+        let t = t + free_flight(self.majorant) / speed;
+        (t < end).then_some(t)
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Range<f64>,
+    ) -> Option<HitRecord> {
+        if self.majorant <= 0. {
+            return None;
+        }
+        let Range { start, end } =
+            self.density.bounds.clip(ray, interval)?;
+
+        // delta tracking: accept tentative collisions in
+        // proportion to the real extinction there
+        let mut rng = rand::thread_rng();
+        let mut t = start;
+        loop {
+            t = self.next_collision(ray, t, end)?;
+            let point = ray.at(t);
+            if rng.gen::<f64>() * self.majorant < self.extinction(point)
+            {
+                // light given off by the absorbing part
+                let emission = self.emission.as_ref().map_or(
+                    DVec3::ZERO,
+                    |(grid, color)| {
+                        (DVec3::ONE - self.albedo)
+                            * *color
+                            * grid.sample(point).max(0.)
+                    },
+                );
+                let material = Material::HenyeyGreenstein {
+                    albedo: self.albedo.into(),
+                    asymmetry: self.asymmetry,
+                    emission,
+                };
+                // a volume has no surface; face the normal at
+                // the viewer, as in `ConstantMedium`
+                return Some(HitRecord::with_face_normal(
+                    material,
+                    point,
+                    -ray.direction.normalize(),
+                    t,
+                    ray,
+                ));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.density.bounds
+    }
+
+    // ratio tracking: every tentative collision lets through
+    // the fraction of it that was fictitious
+    fn transmittance(&self, ray: &Ray, interval: Range<f64>) -> f64 {
+        if self.majorant <= 0. {
+            return 1.;
+        }
+        let Some(Range { start, end }) =
+            self.density.bounds.clip(ray, interval)
+        else {
+            return 1.;
+        };
+
+        let mut transmittance = 1.;
+        let mut t = start;
+        while let Some(next) = self.next_collision(ray, t, end) {
+            t = next;
+            transmittance *=
+                1. - self.extinction(ray.at(t)) / self.majorant;
+        }
+        transmittance
+    }
+}
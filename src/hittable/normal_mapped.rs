@@ -0,0 +1,144 @@
+use std::{io, ops::Range, path::Path, sync::Arc};
+
+use glam::DVec3;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    image::Image,
+    ray::Ray,
+};
+
+/// Fine surface detail stored in an image over the (u, v)
+/// square, repeating outside of it.
+pub enum NormalMap {
+    /// Tangent-space normal map: red, green and blue hold the
+    /// normal's components along the tangent, the bitangent and
+    /// the surface normal, remapped from `[-1, 1]` to `[0, 1]`.
+    /// Green points towards growing `v` (the OpenGL convention).
+    Tangent(Arc<Image>),
+    /// Grayscale height map. `strength` is the slope of a black
+    /// to white change across one texel; larger values make the
+    /// bumps steeper.
+    Bump { height: Arc<Image>, strength: f64 },
+}
+
+impl NormalMap {
+    /// Loads a tangent-space normal map, see `Image::load_raw`.
+    pub fn load_tangent(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(NormalMap::Tangent(Arc::new(Image::load_raw(path)?)))
+    }
+
+    /// Loads a grayscale bump map, see `Image::load_raw`.
+    pub fn load_bump(
+        path: impl AsRef<Path>,
+        strength: f64,
+    ) -> io::Result<Self> {
+        Ok(NormalMap::Bump {
+            height: Arc::new(Image::load_raw(path)?),
+            strength,
+        })
+    }
+
+    /// Outward shading normal at the hit, in world space.
+    fn outward_normal(&self, rec: &HitRecord) -> DVec3 {
+        let outward = if rec.front_face {
+            rec.normal
+        } else {
+            -rec.normal
+        };
+
+        // Gram-Schmidt, since smooth shading tilts the normal
+        // away from the surface the tangents were taken from
+        let tangent = rec.tangent - outward * outward.dot(rec.tangent);
+        let Some(tangent) = tangent.try_normalize() else {
+            return outward;
+        };
+        // mirrored texture coordinates flip the bitangent
+        let mut bitangent = outward.cross(tangent);
+        if bitangent.dot(rec.bitangent) < 0. {
+            bitangent = -bitangent;
+        }
+
+        let (u, v) = (rec.u, rec.v);
+        let perturbed = match self {
+            NormalMap::Tangent(image) => {
+                let c = 2. * sample(image, u, v) - DVec3::ONE;
+                c.x * tangent + c.y * bitangent + c.z * outward
+            }
+            NormalMap::Bump { height, strength } => {
+                let height_at = |u, v| {
+                    sample(height, u, v).dot(DVec3::splat(1. / 3.))
+                };
+                let du = 1. / height.width as f64;
+                let dv = 1. / height.height as f64;
+                let h = height_at(u, v);
+                let slope_u = height_at(u + du, v) - h;
+                let slope_v = height_at(u, v + dv) - h;
+                outward
+                    - *strength
+                        * (slope_u * tangent + slope_v * bitangent)
+            }
+        };
+
+        perturbed.try_normalize().unwrap_or(outward)
+    }
+}
+
+/// Wraps a hittable to perturb its shading normal with a
+/// normal or bump map, using the texture coordinates and
+/// tangents the shape puts in the `HitRecord`.
+///
+/// Only shading changes; the surface stays where it is, so
+/// silhouettes and shadows keep their smooth outline.
+pub struct NormalMapped<H: Hittable> {
+    object: H,
+    map: NormalMap,
+}
+
+impl<H: Hittable> NormalMapped<H> {
+    pub fn new(object: H, map: NormalMap) -> Self {
+        NormalMapped { object, map }
+    }
+}
+
+impl<H: Hittable> Hittable for NormalMapped<H> {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Range<f64>,
+    ) -> Option<HitRecord> {
+        let mut rec = self.object.hit(ray, interval)?;
+
+        let outward = self.map.outward_normal(&rec);
+        let normal = if rec.front_face { outward } else { -outward };
+        // a normal tilted past the viewer would let light
+        // through the surface, keep the geometric one instead
+        if normal.dot(ray.direction) < 0. {
+            rec.normal = normal;
+        }
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.bounding_box()
+    }
+
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        self.object.pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: DVec3) -> DVec3 {
+        self.object.random(origin)
+    }
+
+    fn transmittance(&self, ray: &Ray, interval: Range<f64>) -> f64 {
+        self.object.transmittance(ray, interval)
+    }
+}
+
+/// Repeating lookup, like `Texture::Image`
+fn sample(image: &Image, u: f64, v: f64) -> DVec3 {
+    image.sample(u.rem_euclid(1.), v.rem_euclid(1.))
+}
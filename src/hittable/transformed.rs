@@ -0,0 +1,130 @@
+use std::ops::Range;
+
+use glam::{DAffine3, DMat3, DQuat, DVec3};
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    ray::Ray,
+};
+
+/// Places any hittable in the world with an affine transform:
+/// translation, rotation about any axis, non-uniform scale
+/// and shear.
+///
+/// Rays are moved into object space instead of moving the
+/// object, so the wrapped shape never needs to know.
+pub struct Transformed<H: Hittable> {
+    object: H,
+    /// object space to world space
+    transform: DAffine3,
+    /// world space to object space
+    inverse: DAffine3,
+    /// inverse transpose of the linear part, which keeps
+    /// normals perpendicular under non-uniform scale and shear
+    normal_matrix: DMat3,
+}
+
+impl<H: Hittable> Transformed<H> {
+    pub fn new(object: H, transform: DAffine3) -> Self {
+        let inverse = transform.inverse();
+        Transformed {
+            object,
+            transform,
+            inverse,
+            normal_matrix: inverse.matrix3.transpose(),
+        }
+    }
+
+    /// Applies `transform` after the current one.
+    pub fn then(self, transform: DAffine3) -> Self {
+        Self::new(self.object, transform * self.transform)
+    }
+    pub fn translate(self, offset: DVec3) -> Self {
+        self.then(DAffine3::from_translation(offset))
+    }
+    /// Rotates `angle` radians about `axis` through the origin.
+    pub fn rotate(self, axis: DVec3, angle: f64) -> Self {
+        self.then(DAffine3::from_quat(DQuat::from_axis_angle(
+            axis.normalize(),
+            angle,
+        )))
+    }
+    pub fn scale(self, scale: DVec3) -> Self {
+        self.then(DAffine3::from_scale(scale))
+    }
+
+    pub fn transform(&self) -> DAffine3 {
+        self.transform
+    }
+
+    fn object_ray(&self, ray: &Ray) -> Ray {
+        // The direction is not renormalized, so t means the
+        // same thing in both spaces and the interval carries over
+        Ray {
+            origin: self.inverse.transform_point3(ray.origin),
+            direction: self.inverse.transform_vector3(ray.direction),
+            wavelengths: ray.wavelengths,
+        }
+    }
+}
+
+impl<H: Hittable> Hittable for Transformed<H> {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+        let mut rec = self.object.hit(&self.object_ray(ray), interval)?;
+
+        // front_face stays valid: the dot product of the ray
+        // direction and the normal keeps its sign under this pair
+        // of transforms
+        rec.point = self.transform.transform_point3(rec.point);
+        rec.normal = (self.normal_matrix * rec.normal).normalize();
+        // tangents lie in the surface, so they transform like
+        // any other direction
+        rec.tangent = self.transform.transform_vector3(rec.tangent).normalize();
+        rec.bitangent = self
+            .transform
+            .transform_vector3(rec.bitangent)
+            .normalize();
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let bbox = self.object.bounding_box();
+        if !bbox.is_finite() {
+            return Aabb::new(
+                DVec3::splat(f64::NEG_INFINITY),
+                DVec3::splat(f64::INFINITY),
+            );
+        }
+
+        (0..8).fold(Aabb::EMPTY, |acc, i| {
+            let corner = DVec3::new(
+                if i & 1 == 0 { bbox.min.x } else { bbox.max.x },
+                if i & 2 == 0 { bbox.min.y } else { bbox.max.y },
+                if i & 4 == 0 { bbox.min.z } else { bbox.max.z },
+            );
+            let corner = self.transform.transform_point3(corner);
+            acc.union(&Aabb::new(corner, corner))
+        })
+    }
+
+    // Scale and shear stretch solid angles, so the object's
+    // density picks up the Jacobian of mapping its directions
+    // into world space: |det M⁻¹| / |M⁻¹ ω|³ for a unit ω
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let local = self.inverse.transform_vector3(direction.normalize());
+        let length = local.length();
+        let pdf = self.object.pdf_value(self.inverse.transform_point3(origin), local / length);
+        pdf * self.inverse.matrix3.determinant().abs() / length.powi(3)
+    }
+
+    fn random(&self, origin: DVec3) -> DVec3 {
+        let local = self.object.random(self.inverse.transform_point3(origin));
+        self.transform.transform_vector3(local)
+    }
+
+    fn transmittance(&self, ray: &Ray, interval: Range<f64>) -> f64 {
+        self.object.transmittance(&self.object_ray(ray), interval)
+    }
+}
@@ -0,0 +1,524 @@
+use std::{fmt, fs, io, path::Path};
+
+use glam::DVec3;
+
+/// Grid of linear RGB values, as read from an image file.
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// Row-major, starting at the top-left pixel
+    pub pixels: Vec<DVec3>,
+}
+
+impl Image {
+    /// Reads a binary (`P6`) or ASCII (`P3`) PPM file.
+    ///
+    /// PPM stores gamma-encoded values; they are linearized with
+    /// the same gamma of 2 the camera uses when writing
+    /// `output.ppm`, so a render loads back unchanged.
+    pub fn load_ppm(path: impl AsRef<Path>) -> io::Result<Image> {
+        Self::read_ppm(path.as_ref(), true)
+    }
+
+    fn read_ppm(path: &Path, linearize: bool) -> io::Result<Image> {
+        let bytes = fs::read(path)?;
+        let mut header = TextHeader {
+            bytes: &bytes,
+            position: 0,
+        };
+
+        let magic = header.token()?;
+        let width = header.number()?;
+        let height = header.number()?;
+        let max_value = header.number()?;
+        if max_value == 0 || max_value > 65535 {
+            return Err(invalid_data("PPM max value out of range"));
+        }
+
+        let count = sample_count(width, height, 3)?;
+        let samples: Vec<f64> = match magic.as_str() {
+            // also accepts the fractional values the camera writes
+            "P3" => (0..count)
+                .map(|_| header.sample())
+                .collect::<io::Result<_>>()?,
+            "P6" => {
+                // exactly one whitespace byte separates the
+                // header from the raster
+                let raster = bytes
+                    .get(header.position + 1..)
+                    .unwrap_or_default();
+                let sample_size = if max_value < 256 { 1 } else { 2 };
+                if raster.len() / sample_size < count {
+                    return Err(invalid_data(
+                        "PPM raster is truncated",
+                    ));
+                }
+                raster
+                    .chunks_exact(sample_size)
+                    .take(count)
+                    .map(|chunk| {
+                        chunk.iter().fold(0, |acc, byte| {
+                            acc << 8 | *byte as u32
+                        }) as f64
+                    })
+                    .collect()
+            }
+            _ => return Err(invalid_data("not a P3 or P6 PPM file")),
+        };
+
+        let pixels = samples
+            .chunks_exact(3)
+            .map(|rgb| {
+                let encoded = (DVec3::new(rgb[0], rgb[1], rgb[2])
+                    / max_value as f64)
+                    .clamp(DVec3::ZERO, DVec3::ONE);
+                if linearize {
+                    encoded * encoded
+                } else {
+                    encoded
+                }
+            })
+            .collect();
+
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Reads a color (`PF`) or grayscale (`Pf`) Portable Float
+    /// Map. Values are linear already.
+    pub fn load_pfm(path: impl AsRef<Path>) -> io::Result<Image> {
+        let bytes = fs::read(path)?;
+        let mut header = TextHeader {
+            bytes: &bytes,
+            position: 0,
+        };
+
+        let channels = match header.token()?.as_str() {
+            "PF" => 3,
+            "Pf" => 1,
+            _ => return Err(invalid_data("not a PF or Pf PFM file")),
+        };
+        let width = header.number()?;
+        let height = header.number()?;
+        // the sign of the scale gives the byte order
+        let little_endian = header.sample()? < 0.;
+
+        let raster =
+            bytes.get(header.position + 1..).unwrap_or_default();
+        let count = sample_count(width, height, channels)?;
+        if raster.len() / 4 < count {
+            return Err(invalid_data("PFM raster is truncated"));
+        }
+        let samples: Vec<f64> = raster
+            .chunks_exact(4)
+            .take(count)
+            .map(|chunk| {
+                let bytes = [chunk[0], chunk[1], chunk[2], chunk[3]];
+                if little_endian {
+                    f32::from_le_bytes(bytes) as f64
+                } else {
+                    f32::from_be_bytes(bytes) as f64
+                }
+            })
+            .collect();
+
+        // rows are stored bottom to top; an empty image has none
+        let mut pixels = Vec::with_capacity(count / channels);
+        let row_length = (width * channels).max(1);
+        for row in samples.chunks_exact(row_length).rev() {
+            pixels.extend(row.chunks_exact(channels).map(|c| {
+                if channels == 3 {
+                    DVec3::new(c[0], c[1], c[2])
+                } else {
+                    DVec3::splat(c[0])
+                }
+            }));
+        }
+
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Reads a Radiance `.hdr` (RGBE) file, flat or run-length
+    /// encoded, in the usual `-Y height +X width` orientation.
+    pub fn load_hdr(path: impl AsRef<Path>) -> io::Result<Image> {
+        let bytes = fs::read(path)?;
+        let mut position = 0;
+        let mut next_line = || {
+            let rest = bytes.get(position..).unwrap_or_default();
+            let end = rest.iter().position(|byte| *byte == b'\n')?;
+            position += end + 1;
+            Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+        };
+
+        let magic = next_line().unwrap_or_default();
+        if !magic.starts_with("#?") {
+            return Err(invalid_data("not a Radiance HDR file"));
+        }
+        // header variables end at the first empty line
+        loop {
+            match next_line() {
+                Some(line) if line.trim().is_empty() => break,
+                Some(line) => {
+                    if let Some(format) = line.strip_prefix("FORMAT=") {
+                        if format.trim() != "32-bit_rle_rgbe" {
+                            return Err(invalid_data(
+                                "only RGBE HDR files are supported",
+                            ));
+                        }
+                    }
+                }
+                None => {
+                    return Err(invalid_data("HDR header is truncated"))
+                }
+            }
+        }
+
+        let resolution = next_line().unwrap_or_default();
+        let (height, width) =
+            match resolution.split_whitespace().collect::<Vec<_>>()[..]
+            {
+                ["-Y", height, "+X", width] => (
+                    height.parse::<usize>().map_err(|_| {
+                        invalid_data("invalid HDR resolution")
+                    })?,
+                    width.parse::<usize>().map_err(|_| {
+                        invalid_data("invalid HDR resolution")
+                    })?,
+                ),
+                _ => {
+                    return Err(invalid_data(
+                        "unsupported HDR orientation, expected -Y +X",
+                    ))
+                }
+            };
+
+        // Even run-length encoded, a scanline takes at least 8
+        // bytes per 127 pixels, which bounds what the header can
+        // claim before anything is allocated
+        let pixel_count = sample_count(width, height, 1)?;
+        if pixel_count.max(width) / 16 > bytes.len() - position {
+            return Err(invalid_data("HDR data is truncated"));
+        }
+
+        let mut data = Rgbe {
+            bytes: &bytes,
+            position,
+        };
+        let mut pixels = Vec::with_capacity(pixel_count);
+        let mut scanline = vec![[0u8; 4]; width];
+        for _ in 0..height {
+            data.read_scanline(&mut scanline)?;
+            pixels.extend(scanline.iter().map(|rgbe| {
+                if rgbe[3] == 0 {
+                    return DVec3::ZERO;
+                }
+                let scale = 2f64.powi(rgbe[3] as i32 - (128 + 8));
+                DVec3::new(
+                    rgbe[0] as f64,
+                    rgbe[1] as f64,
+                    rgbe[2] as f64,
+                ) * scale
+            }));
+        }
+
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Reads an 8 or 16-bit PNG file, linearizing its sRGB
+    /// values. Alpha is ignored.
+    pub fn load_png(path: impl AsRef<Path>) -> io::Result<Image> {
+        Self::read_png(path.as_ref(), true)
+    }
+
+    fn read_png(path: &Path, linearize: bool) -> io::Result<Image> {
+        let mut decoder = png::Decoder::new(fs::File::open(path)?);
+        // palettes and low bit depths become 8-bit samples
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder
+            .read_info()
+            .map_err(|error| invalid_data(&error.to_string()))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader
+            .next_frame(&mut buffer)
+            .map_err(|error| invalid_data(&error.to_string()))?;
+
+        let channels = info.color_type.samples();
+        let (sample_size, max_value) = match info.bit_depth {
+            png::BitDepth::Sixteen => (2, 65535.),
+            _ => (1, 255.),
+        };
+        let (width, height) =
+            (info.width as usize, info.height as usize);
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in buffer.chunks_exact(info.line_size).take(height) {
+            let samples: Vec<f64> = row
+                .chunks_exact(sample_size)
+                .map(|chunk| {
+                    let value = chunk
+                        .iter()
+                        .fold(0, |acc, byte| acc << 8 | *byte as u32);
+                    let encoded = value as f64 / max_value;
+                    if linearize {
+                        srgb_to_linear(encoded)
+                    } else {
+                        encoded
+                    }
+                })
+                .collect();
+            pixels.extend(
+                samples.chunks_exact(channels).take(width).map(|c| {
+                    // gray with or without alpha, or RGB(A)
+                    if channels < 3 {
+                        DVec3::splat(c[0])
+                    } else {
+                        DVec3::new(c[0], c[1], c[2])
+                    }
+                }),
+            );
+        }
+
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Loads a PPM, PNG, PFM or Radiance HDR file, picked by
+    /// extension.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Image> {
+        Self::read(path.as_ref(), true)
+    }
+
+    /// Like `load`, but keeps the stored values of PPM and PNG
+    /// files as they are (scaled to `[0, 1]`) instead of treating
+    /// them as gamma-encoded colors. For images that hold data,
+    /// such as normal and bump maps.
+    pub fn load_raw(path: impl AsRef<Path>) -> io::Result<Image> {
+        Self::read(path.as_ref(), false)
+    }
+
+    fn read(path: &Path, linearize: bool) -> io::Result<Image> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Self::read_ppm(path, linearize),
+            "png" => Self::read_png(path, linearize),
+            "pfm" => Self::load_pfm(path),
+            "hdr" | "pic" => Self::load_hdr(path),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("unsupported image format `{extension}`"),
+            )),
+        }
+    }
+
+    /// Pixel at column `x`, row `y`, clamped to the edges.
+    pub fn pixel(&self, x: usize, y: usize) -> DVec3 {
+        let x = x.min(self.width.saturating_sub(1));
+        let y = y.min(self.height.saturating_sub(1));
+        self.pixels
+            .get(y * self.width + x)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Bilinearly filtered color at texture coordinates in
+    /// `[0, 1]`, with (0, 0) at the bottom-left corner.
+    pub fn sample(&self, u: f64, v: f64) -> DVec3 {
+        // continuous pixel coordinates, pixel centers at +0.5
+        let x = u.clamp(0., 1.) * self.width as f64 - 0.5;
+        let y = (1. - v.clamp(0., 1.)) * self.height as f64 - 0.5;
+        let x0 = x.floor().max(0.);
+        let y0 = y.floor().max(0.);
+        let tx = (x - x0).clamp(0., 1.);
+        let ty = (y - y0).clamp(0., 1.);
+        let (x0, y0) = (x0 as usize, y0 as usize);
+
+        let top = self.pixel(x0, y0).lerp(self.pixel(x0 + 1, y0), tx);
+        let bottom =
+            self.pixel(x0, y0 + 1).lerp(self.pixel(x0 + 1, y0 + 1), tx);
+        top.lerp(bottom, ty)
+    }
+}
+
+// The pixels would drown out everything else
+impl fmt::Debug for Image {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Image")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The sRGB transfer curve, from encoded values in `[0, 1]`
+fn srgb_to_linear(encoded: f64) -> f64 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Samples in a raster of the given size, or an error if a
+/// header claims more than could ever be addressed
+fn sample_count(
+    width: usize,
+    height: usize,
+    channels: usize,
+) -> io::Result<usize> {
+    width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or_else(|| invalid_data("image resolution too large"))
+}
+
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct TextHeader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl TextHeader<'_> {
+    /// Next whitespace-separated token, skipping `#` comments.
+    fn token(&mut self) -> io::Result<String> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(b'#') => {
+                    while !matches!(
+                        self.bytes.get(self.position),
+                        None | Some(b'\n')
+                    ) {
+                        self.position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => {
+                    self.position += 1
+                }
+                Some(_) => break,
+                None => {
+                    return Err(invalid_data(
+                        "unexpected end of PPM file",
+                    ))
+                }
+            }
+        }
+
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.position])
+            .into_owned())
+    }
+
+    fn sample(&mut self) -> io::Result<f64> {
+        let token = self.token()?;
+        token.parse().map_err(|_| {
+            invalid_data(&format!("invalid PPM sample `{token}`"))
+        })
+    }
+
+    fn number(&mut self) -> io::Result<usize> {
+        let token = self.token()?;
+        token.parse().map_err(|_| {
+            invalid_data(&format!("invalid PPM number `{token}`"))
+        })
+    }
+}
+
+/// Reader for the pixel data of a Radiance HDR file
+struct Rgbe<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Rgbe<'_> {
+    fn byte(&mut self) -> io::Result<u8> {
+        let byte = self.bytes.get(self.position).copied().ok_or_else(
+            || invalid_data("HDR pixel data is truncated"),
+        )?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn read_scanline(
+        &mut self,
+        scanline: &mut [[u8; 4]],
+    ) -> io::Result<()> {
+        let width = scanline.len();
+        let run_length_encoded = (8..=0x7fff).contains(&width)
+            && self.bytes.get(self.position..self.position + 2)
+                == Some(&[2, 2]);
+
+        if !run_length_encoded {
+            for pixel in scanline.iter_mut() {
+                for channel in pixel.iter_mut() {
+                    *channel = self.byte()?;
+                }
+            }
+            return Ok(());
+        }
+
+        let header =
+            [self.byte()?, self.byte()?, self.byte()?, self.byte()?];
+        if ((header[2] as usize) << 8 | header[3] as usize) != width {
+            return Err(invalid_data("HDR scanline width mismatch"));
+        }
+
+        // each channel is run-length encoded separately
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = self.byte()? as usize;
+                if count > 128 {
+                    let count = count - 128;
+                    let value = self.byte()?;
+                    if x + count > width {
+                        return Err(invalid_data(
+                            "HDR run overflows scanline",
+                        ));
+                    }
+                    for pixel in &mut scanline[x..x + count] {
+                        pixel[channel] = value;
+                    }
+                    x += count;
+                } else {
+                    if count == 0 || x + count > width {
+                        return Err(invalid_data(
+                            "invalid HDR run length",
+                        ));
+                    }
+                    for pixel in &mut scanline[x..x + count] {
+                        pixel[channel] = self.byte()?;
+                    }
+                    x += count;
+                }
+            }
+        }
+        Ok(())
+    }
+}
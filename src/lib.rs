@@ -1,5 +1,14 @@
+pub mod aabb;
+pub mod background;
 pub mod camera;
 pub mod hittable;
+pub mod image;
+pub mod lights;
 pub mod material;
+pub mod obj;
 pub mod ray;
-pub mod shapes;
\ No newline at end of file
+pub mod scene;
+pub mod shapes;
+pub mod spectrum;
+pub mod texture;
+pub mod voxel_grid;
@@ -0,0 +1,22 @@
+use glam::DVec3;
+
+use super::{Light, LightSample};
+
+/// Light from an infinitely distant source such as the sun:
+/// parallel rays and no falloff.
+pub struct DirectionalLight {
+    /// Direction the light travels in
+    pub direction: DVec3,
+    pub color: DVec3,
+    pub intensity: f64,
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: DVec3) -> LightSample {
+        LightSample {
+            direction: -self.direction.normalize(),
+            distance: f64::INFINITY,
+            radiance: self.color * self.intensity,
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use glam::DVec3;
+
+pub mod directional;
+pub mod point;
+pub mod spot;
+
+/// Light source that isn't part of the geometry. The renderer
+/// samples these directly with a shadow ray from every diffuse
+/// hit instead of waiting for a bounce to find them.
+pub trait Light: Send + Sync {
+    /// Light arriving at `point` from this source, ignoring
+    /// anything in between.
+    fn sample(&self, point: DVec3) -> LightSample;
+}
+
+pub struct LightSample {
+    /// Unit vector from the lit point towards the light
+    pub direction: DVec3,
+    /// Distance to the light, infinite for lights that are
+    /// infinitely far away
+    pub distance: f64,
+    /// Radiance arriving along `direction`
+    pub radiance: DVec3,
+}
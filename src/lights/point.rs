@@ -0,0 +1,24 @@
+use glam::DVec3;
+
+use super::{Light, LightSample};
+
+/// Light radiating equally in all directions from a single
+/// point, falling off with the square of the distance.
+pub struct PointLight {
+    pub position: DVec3,
+    pub color: DVec3,
+    pub intensity: f64,
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: DVec3) -> LightSample {
+        let to_light = self.position - point;
+        let distance_squared = to_light.length_squared();
+
+        LightSample {
+            direction: to_light.normalize(),
+            distance: distance_squared.sqrt(),
+            radiance: self.color * self.intensity / distance_squared,
+        }
+    }
+}
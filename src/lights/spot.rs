@@ -0,0 +1,52 @@
+use glam::DVec3;
+
+use super::{Light, LightSample};
+
+/// Point light restricted to a cone. Full intensity inside
+/// `inner_angle`, nothing outside `outer_angle`, with a smooth
+/// penumbra in between. Angles are measured from `direction`
+/// in radians.
+pub struct SpotLight {
+    pub position: DVec3,
+    /// Direction the cone points in
+    pub direction: DVec3,
+    pub color: DVec3,
+    pub intensity: f64,
+    pub inner_angle: f64,
+    pub outer_angle: f64,
+}
+
+impl SpotLight {
+    /// How much of the light leaves in `direction`, from 0 to 1
+    fn falloff(&self, direction: DVec3) -> f64 {
+        let cos_theta = direction.dot(self.direction.normalize());
+        let cos_outer = self.outer_angle.cos();
+        let cos_inner = self.inner_angle.cos().max(cos_outer);
+
+        if cos_inner - cos_outer <= f64::EPSILON {
+            // no penumbra, hard edge
+            return if cos_theta >= cos_outer { 1. } else { 0. };
+        }
+        let x = ((cos_theta - cos_outer) / (cos_inner - cos_outer))
+            .clamp(0., 1.);
+        // smoothstep
+        x * x * (3. - 2. * x)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: DVec3) -> LightSample {
+        let to_light = self.position - point;
+        let distance_squared = to_light.length_squared();
+        let direction = to_light.normalize();
+
+        LightSample {
+            direction,
+            distance: distance_squared.sqrt(),
+            radiance: self.color
+                * self.intensity
+                * self.falloff(-direction)
+                / distance_squared,
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use glam::DVec3;
 use rt::hittable::Hittable;
-use rt::{camera::Camera, material::Material, shapes::cylinder::Cylinder, shapes::sphere::Sphere};
+use rt::{camera::Camera, material::Material, shapes::cube::Cuboid, shapes::cylinder::Cylinder, shapes::plane::Plane, shapes::sphere::Sphere};
 use std::cell::RefCell;
 use std::io;
 use std::rc::Rc;
@@ -16,6 +16,7 @@ struct AppState {
 }
 
 struct ObjectConfig {
+    object_selector: Rc<RefCell<ComboBoxText>>,
     pos_x_entry: Rc<RefCell<Entry>>,
     pos_y_entry: Rc<RefCell<Entry>>,
     pos_z_entry: Rc<RefCell<Entry>>,
@@ -39,6 +40,7 @@ struct GuiData {
 
 fn create_object_config() -> (
     GtkBox,
+    Rc<RefCell<ComboBoxText>>,
     Rc<RefCell<Entry>>,
     Rc<RefCell<Entry>>,
     Rc<RefCell<Entry>>,
@@ -52,6 +54,7 @@ fn create_object_config() -> (
     object_selector.borrow_mut().append_text("Sphere");
     object_selector.borrow_mut().append_text("Cube");
     object_selector.borrow_mut().append_text("Cylinder");
+    object_selector.borrow_mut().append_text("Flat Plane");
     object_selector.borrow_mut().set_active(Some(0));
     let object_selector = object_selector.clone();
 
@@ -113,6 +116,7 @@ fn create_object_config() -> (
     // Return all the required elements
     (
         hbox,
+        object_selector,
         pos_x_entry,
         pos_y_entry,
         pos_z_entry,
@@ -179,10 +183,11 @@ fn launch_gui() {
 
 
         // Create a new object configuration section
-        let (object_config, pos_x_entry, pos_y_entry, pos_z_entry, radius_entry, material_selector) = create_object_config();
+        let (object_config, object_selector, pos_x_entry, pos_y_entry, pos_z_entry, radius_entry, material_selector) = create_object_config();
         vbox_clone.pack_start(&object_config, false, false, 10);
 
     app_state.borrow_mut().object_configs.push(ObjectConfig {
+        object_selector: object_selector.clone(),
         pos_x_entry: pos_x_entry.clone(),
         pos_y_entry: pos_y_entry.clone(),
         pos_z_entry: pos_z_entry.clone(),
@@ -259,6 +264,10 @@ fn launch_gui() {
     //Button to render the scene with the given parameters from the GUI on click
     render_button.connect_clicked(clone!(@strong app_state => move |_| {
         
+        // Every object section goes into one scene, rendered once
+        let mut world: Vec<Box<dyn Hittable>> = vec![];
+        let mut camera: Option<Camera> = None;
+
         // Iterate over all stored object configurations
         for object_config in &app_state.borrow().object_configs {
             // Read values from each object configuration
@@ -270,25 +279,32 @@ fn launch_gui() {
                 .borrow()
                 .get_active_text()
                 .map_or_else(|| "".to_string(), |gstr| gstr.to_string());
+            let object_type = object_config.object_selector
+                .borrow()
+                .get_active_text()
+                .map_or_else(|| "Sphere".to_string(), |gstr| gstr.to_string());
     
             // Create GuiData for the current object
             let gui_data = GuiData {
-                object_type: material.clone(), // Assuming object type is determined by material for simplicity
+                object_type,
                 object_position: (pos_x, pos_y, pos_z),
                 object_radius: radius,
                 object_material: match material.as_str() {
                 "Dielectric" => Material::Dielectric {
-                    index_of_refraction: 1.5,
+                    index_of_refraction: 1.5.into(),
+                    absorption: DVec3::ZERO,
+                    film: None,
                 }, // Adjust as needed
                 "Lambertian" => Material::Lambertian {
-                    albedo: DVec3::new(0.5, 0.5, 0.5),
+                    albedo: DVec3::new(0.5, 0.5, 0.5).into(),
                 }, // Adjust as needed
                 "Metal" => Material::Metal {
-                    albedo: DVec3::new(0.7, 0.6, 0.5),
+                    albedo: DVec3::new(0.7, 0.6, 0.5).into(),
                     fuzz: 0.0,
+                    film: None,
                 }, // Adjust as needed
                 _ => Material::Lambertian {
-                    albedo: DVec3::new(0.5, 0.5, 0.5),
+                    albedo: DVec3::new(0.5, 0.5, 0.5).into(),
                 }, // Default
             },
             camera_position: (
@@ -301,11 +317,8 @@ fn launch_gui() {
             aspect_ratio: 16.0 / 9.0, // Adjust as needed
         };
 
-        let world: Vec<Box<dyn Hittable>> = create_world_from_gui_data(&gui_data);
-        let camera: Camera = create_camera_from_gui_data(&gui_data);
-
-        // Trigger the rendering logic:
-        camera.render_to_disk(world).unwrap();
+        world.extend(create_world_from_gui_data(&gui_data));
+        camera = Some(create_camera_from_gui_data(&gui_data));
 
         // Log the rendering information
         println!("Rendering with the following parameters:");
@@ -316,6 +329,12 @@ fn launch_gui() {
         println!("Image Width: {:?}", gui_data.image_width);
         //println!("Raw X position2: {:?}", pos_x_entry.borrow().get_text());
     }
+
+        // Trigger the rendering logic:
+        match camera {
+            Some(camera) => camera.render_to_disk(world).unwrap(),
+            None => eprintln!("Add an object before rendering"),
+        }
     }));
 
     window.add(&vbox);
@@ -331,18 +350,44 @@ fn launch_gui() {
 fn create_world_from_gui_data(gui_data: &GuiData) -> Vec<Box<dyn Hittable>> {
     let mut world: Vec<Box<dyn Hittable>> = vec![];
 
-    // For now, adding one object based on GUI data
-    world.push(Box::new(Sphere {
-        center: DVec3::new(
-            gui_data.object_position.0,
-            gui_data.object_position.1,
-            gui_data.object_position.2,
-        ),
-        radius: gui_data.object_radius,
-        material: gui_data.object_material.clone(),
-    }));
-
-    // TODO: Add more objects based on GUI data
+    let center = DVec3::new(
+        gui_data.object_position.0,
+        gui_data.object_position.1,
+        gui_data.object_position.2,
+    );
+    let radius = gui_data.object_radius;
+    let material = gui_data.object_material.clone();
+
+    match gui_data.object_type.as_str() {
+        // Flat planes are horizontal ground planes through the given position
+        "Flat Plane" => world.push(Box::new(Plane {
+            point: center,
+            normal: DVec3::Y,
+            material,
+        })),
+        // The cube "radius" is the distance from its center to each face
+        "Cube" => world.push(Box::new(Cuboid::from_center(
+            center,
+            DVec3::splat(radius),
+            material,
+        ))),
+        // Without a height entry, cylinders are as tall as they are wide
+        "Cylinder" => world.push(Box::new(Cylinder {
+            center,
+            height: 2.0 * radius,
+            radius,
+            material,
+            translation: DVec3::ZERO,
+            rotation: 0.0,
+            inner_radius: 0.0,
+            open_ended: false,
+        })),
+        _ => world.push(Box::new(Sphere {
+            center,
+            radius,
+            material,
+        })),
+    }
 
     world
 }
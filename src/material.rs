@@ -1,24 +1,356 @@
-use crate::{hittable::HitRecord, ray::Ray};
+use crate::{
+    hittable::HitRecord,
+    ray::Ray,
+    spectrum::{self, Wavelengths},
+    texture::Texture,
+};
 use glam::DVec3;
 
 use rand::Rng;
 use reflections::*;
+mod microfacet;
+mod phase;
+mod principled;
 mod reflections;
 mod vectors;
 use vectors::*;
 
+pub use principled::Principled;
+
+/// Density of directions spread evenly over the sphere
+const ISOTROPIC_PDF: f64 = 0.25 * std::f64::consts::FRAC_1_PI;
+
 #[non_exhaustive]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Material {
-    Lambertian { albedo: DVec3 },
-    Metal { albedo: DVec3, fuzz: f64 },
-    Dielectric { index_of_refraction: f64 },
+    Lambertian { albedo: Texture },
+    /// `film` gives it an iridescent coating, like heat-tinted
+    /// steel.
+    Metal {
+        albedo: Texture,
+        fuzz: f64,
+        film: Option<ThinFilm>,
+    },
+    /// Clear or tinted glass. Light travelling through it is
+    /// absorbed at the rate `absorption` per unit distance and
+    /// channel (Beer-Lambert), so thick parts look darker than
+    /// thin ones; zero keeps it perfectly clear. `film` coats
+    /// it, like a lens; with an index of 1 it makes a soap
+    /// bubble.
+    Dielectric {
+        index_of_refraction: RefractiveIndex,
+        absorption: DVec3,
+        film: Option<ThinFilm>,
+    },
+    /// Light source surface: emits `color * intensity` and
+    /// doesn't scatter
+    Emissive { color: DVec3, intensity: f64 },
+    /// Physically based metal: a GGX microfacet surface with
+    /// the Fresnel reflectance of a complex index of refraction.
+    /// `roughness` runs from 0 (mirror) to 1.
+    Conductor { ior: ComplexIor, roughness: f64 },
+    /// Frosted glass: a GGX microfacet dielectric that both
+    /// reflects and refracts. `roughness` runs from 0 (clear,
+    /// like `Dielectric`) to 1.
+    RoughDielectric {
+        index_of_refraction: f64,
+        roughness: f64,
+    },
+    /// Layered all-purpose surface in the style of the
+    /// Disney and Blender principled BSDFs, for scenes coming
+    /// from other tools.
+    Principled(Principled),
+    /// Phase function of a participating medium such as
+    /// `ConstantMedium`: scatters equally in every direction,
+    /// keeping `albedo` of the light.
+    Isotropic { albedo: Texture },
+    /// Phase function of a medium that scatters mostly forward
+    /// (`asymmetry` above 0) or back (below 0), like clouds and
+    /// smoke, see `GridMedium`. The medium gives off `emission`
+    /// at the collision, for fire.
+    HenyeyGreenstein {
+        albedo: Texture,
+        asymmetry: f64,
+        emission: DVec3,
+    },
+}
+
+/// Complex index of refraction `eta + i k` of a metal, per
+/// color channel (red, green and blue at about 650, 550 and
+/// 450 nm).
+#[derive(Debug, Clone, Copy)]
+pub struct ComplexIor {
+    pub eta: DVec3,
+    pub k: DVec3,
+}
+
+impl ComplexIor {
+    pub const GOLD: ComplexIor = ComplexIor {
+        eta: DVec3::new(0.18299, 0.42108, 1.37340),
+        k: DVec3::new(3.42420, 2.34590, 1.77040),
+    };
+    pub const COPPER: ComplexIor = ComplexIor {
+        eta: DVec3::new(0.27105, 0.67693, 1.31640),
+        k: DVec3::new(3.60920, 2.62480, 2.29210),
+    };
+    pub const ALUMINIUM: ComplexIor = ComplexIor {
+        eta: DVec3::new(1.65746, 0.88037, 0.52123),
+        k: DVec3::new(9.22387, 6.26952, 4.83700),
+    };
+}
+
+/// Coating a fraction of a wavelength thick, whose reflections
+/// interfere into shifting rainbow colors.
+#[derive(Debug, Clone, Copy)]
+pub struct ThinFilm {
+    /// Thickness in nanometers; visible colors need a few
+    /// hundred
+    pub thickness: f64,
+    pub index_of_refraction: f64,
+}
+
+impl ThinFilm {
+    /// Reflectance for light at the ray's wavelengths, given
+    /// the reflectance at a single one. In spectral mode the
+    /// path splits up by wavelength, so only the hero is kept.
+    fn reflectance(
+        wavelengths: Option<Wavelengths>,
+        at: impl Fn(f64) -> DVec3,
+    ) -> (DVec3, Option<Wavelengths>) {
+        match wavelengths {
+            Some(wavelengths) => {
+                (at(wavelengths.hero()), Some(wavelengths.hero_only()))
+            }
+            // colors past the sRGB gamut would come out
+            // negative
+            None => (
+                spectrum::reflectance_to_rgb(at)
+                    .clamp(DVec3::ZERO, DVec3::ONE),
+                None,
+            ),
+        }
+    }
+}
+
+/// Index of refraction of a dielectric, which may vary with
+/// wavelength. Dispersion only shows in spectral mode; RGB
+/// rendering uses the nominal index.
+#[derive(Debug, Clone, Copy)]
+pub enum RefractiveIndex {
+    Constant(f64),
+    /// `a + b / λ²`, with λ in micrometers
+    Cauchy { a: f64, b: f64 },
+    /// `n² = 1 + Σ b λ² / (λ² - c)`, with λ in micrometers
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl From<f64> for RefractiveIndex {
+    fn from(index: f64) -> Self {
+        RefractiveIndex::Constant(index)
+    }
+}
+
+impl RefractiveIndex {
+    /// Borosilicate crown glass, the usual optical glass
+    pub const BK7: RefractiveIndex = RefractiveIndex::Sellmeier {
+        b: [1.03961212, 0.231792344, 1.01046945],
+        c: [0.00600069867, 0.0200179144, 103.560653],
+    };
+    pub const FUSED_SILICA: RefractiveIndex =
+        RefractiveIndex::Sellmeier {
+            b: [0.6961663, 0.4079426, 0.8974794],
+            c: [0.00467914826, 0.0135120631, 97.9340025],
+        };
+    /// Strongly dispersive, hence the fire of a cut diamond
+    pub const DIAMOND: RefractiveIndex = RefractiveIndex::Sellmeier {
+        b: [0.3306, 4.3356, 0.],
+        c: [0.030625, 0.011236, 0.],
+    };
+
+    /// Index at `wavelength` nanometers.
+    pub fn at(&self, wavelength: f64) -> f64 {
+        let micrometers = wavelength * 1e-3;
+        let l2 = micrometers * micrometers;
+        match *self {
+            RefractiveIndex::Constant(index) => index,
+            RefractiveIndex::Cauchy { a, b } => a + b / l2,
+            RefractiveIndex::Sellmeier { b, c } => (1.
+                + (0..3)
+                    .map(|i| b[i] * l2 / (l2 - c[i]))
+                    .sum::<f64>())
+            .sqrt(),
+        }
+    }
+
+    /// Index at the yellow sodium d line (587.6 nm), the one
+    /// glass catalogs quote.
+    pub fn nominal(&self) -> f64 {
+        self.at(587.56)
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, RefractiveIndex::Constant(_))
+    }
 }
+
 pub struct Scattered {
     pub attenuation: DVec3,
     pub scattered: Ray,
+    /// Density the direction was sampled with, or `None` for
+    /// perfectly specular bounces that can't be light sampled
+    pub pdf: Option<f64>,
 }
 impl Material {
+    /// Glass that filters light down to `color` over every
+    /// `distance` units it travels inside.
+    pub fn tinted_glass(
+        index_of_refraction: impl Into<RefractiveIndex>,
+        color: DVec3,
+        distance: f64,
+    ) -> Self {
+        Material::Dielectric {
+            index_of_refraction: index_of_refraction.into(),
+            absorption: absorption(color, distance),
+            film: None,
+        }
+    }
+
+    /// Radiance the surface gives off by itself at the hit point.
+    pub fn emitted(&self, _hit_record: &HitRecord) -> DVec3 {
+        match self {
+            Material::Emissive { color, intensity } => {
+                *color * *intensity
+            }
+            Material::HenyeyGreenstein { emission, .. } => *emission,
+            _ => DVec3::ZERO,
+        }
+    }
+
+    /// BSDF times the cosine term for light arriving from
+    /// `direction` (a unit vector away from the surface). Zero
+    /// for materials that only scatter in a handful of exact
+    /// directions, since sampled light can never line up with them.
+    pub fn eval(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        direction: DVec3,
+    ) -> DVec3 {
+        let wo = -r_in.direction.normalize();
+        match self {
+            Material::Lambertian { albedo } => {
+                let cos_theta =
+                    hit_record.normal.dot(direction).max(0.);
+                albedo.value(hit_record.u, hit_record.v, hit_record.point)
+                    * cos_theta
+                    * std::f64::consts::FRAC_1_PI
+            }
+            // a phase function has no surface to take a cosine
+            // against
+            Material::Isotropic { albedo } => {
+                albedo.value(hit_record.u, hit_record.v, hit_record.point)
+                    * ISOTROPIC_PDF
+            }
+            Material::HenyeyGreenstein {
+                albedo, asymmetry, ..
+            } => {
+                albedo.value(hit_record.u, hit_record.v, hit_record.point)
+                    * phase::henyey_greenstein(
+                        (-wo).dot(direction),
+                        *asymmetry,
+                    )
+            }
+            Material::Conductor { ior, roughness } => {
+                microfacet::conductor_eval(
+                    ior,
+                    *roughness,
+                    hit_record.normal,
+                    wo,
+                    direction,
+                )
+            }
+            Material::RoughDielectric {
+                index_of_refraction,
+                roughness,
+            } => DVec3::splat(microfacet::dielectric_eval(
+                relative_index(hit_record, *index_of_refraction),
+                *roughness,
+                hit_record.normal,
+                wo,
+                direction,
+            )),
+            Material::Principled(principled) => principled.eval(
+                principled.base_color.value(
+                    hit_record.u,
+                    hit_record.v,
+                    hit_record.point,
+                ),
+                relative_index(
+                    hit_record,
+                    principled.index_of_refraction,
+                ),
+                hit_record.normal,
+                wo,
+                direction,
+            ),
+            _ => DVec3::ZERO,
+        }
+    }
+
+    /// Density with which `scatter` would pick `direction`.
+    pub fn pdf(
+        &self,
+        r_in: &Ray,
+        hit_record: &HitRecord,
+        direction: DVec3,
+    ) -> f64 {
+        let wo = -r_in.direction.normalize();
+        match self {
+            Material::Lambertian { .. } => {
+                let cos_theta =
+                    hit_record.normal.dot(direction).max(0.);
+                cos_theta * std::f64::consts::FRAC_1_PI
+            }
+            Material::Isotropic { .. } => ISOTROPIC_PDF,
+            Material::HenyeyGreenstein { asymmetry, .. } => {
+                phase::henyey_greenstein((-wo).dot(direction), *asymmetry)
+            }
+            Material::Conductor { roughness, .. } => {
+                microfacet::conductor_pdf(
+                    *roughness,
+                    hit_record.normal,
+                    wo,
+                    direction,
+                )
+            }
+            Material::RoughDielectric {
+                index_of_refraction,
+                roughness,
+            } => microfacet::dielectric_pdf(
+                relative_index(hit_record, *index_of_refraction),
+                *roughness,
+                hit_record.normal,
+                wo,
+                direction,
+            ),
+            Material::Principled(principled) => principled.pdf(
+                principled.base_color.value(
+                    hit_record.u,
+                    hit_record.v,
+                    hit_record.point,
+                ),
+                relative_index(
+                    hit_record,
+                    principled.index_of_refraction,
+                ),
+                hit_record.normal,
+                wo,
+                direction,
+            ),
+            _ => 0.,
+        }
+    }
+
     pub fn scatter(
         &self,
         r_in: &Ray,
@@ -41,22 +373,67 @@ impl Material {
                 let scattered = Ray {
                     origin: hit_record.point,
                     direction: scatter_direction,
+                    wavelengths: r_in.wavelengths,
                 };
 
+                // normal + unit vector is cosine distributed
+                let pdf = self.pdf(
+                    r_in,
+                    &hit_record,
+                    scattered.direction.normalize(),
+                );
+
                 Some(Scattered {
-                    attenuation: *albedo,
+                    attenuation: albedo.value(
+                        hit_record.u,
+                        hit_record.v,
+                        hit_record.point,
+                    ),
                     scattered,
+                    pdf: Some(pdf),
                 })
             }
-            Material::Metal { albedo, fuzz } => {
-                let reflected: DVec3 = reflect(
-                    r_in.direction.normalize(),
-                    hit_record.normal,
+            Material::Metal { albedo, fuzz, film } => {
+                let unit_direction = r_in.direction.normalize();
+                let reflected: DVec3 =
+                    reflect(unit_direction, hit_record.normal);
+                let albedo = albedo.value(
+                    hit_record.u,
+                    hit_record.v,
+                    hit_record.point,
                 );
+                let (attenuation, wavelengths) = match film {
+                    Some(film) => {
+                        let cos_theta =
+                            -unit_direction.dot(hit_record.normal);
+                        ThinFilm::reflectance(
+                            r_in.wavelengths,
+                            |wavelength| {
+                                let channel = |reflectance| {
+                                    thin_film_mirror(
+                                        cos_theta,
+                                        1.,
+                                        film.index_of_refraction,
+                                        film.thickness,
+                                        reflectance,
+                                        wavelength,
+                                    )
+                                };
+                                DVec3::new(
+                                    channel(albedo.x),
+                                    channel(albedo.y),
+                                    channel(albedo.z),
+                                )
+                            },
+                        )
+                    }
+                    None => (albedo, r_in.wavelengths),
+                };
                 let scattered = Ray {
                     origin: hit_record.point,
                     direction: reflected
                         + *fuzz * random_unit_vector(),
+                    wavelengths,
                 };
                 // absorb any scatter that is below the surface
                 if scattered
@@ -64,9 +441,12 @@ impl Material {
                     .dot(hit_record.normal)
                     > 0.
                 {
+                    // the fuzz lobe has no closed-form density,
+                    // so it is treated like a mirror
                     Some(Scattered {
-                        attenuation: *albedo,
+                        attenuation,
                         scattered,
+                        pdf: None,
                     })
                 } else {
                     None
@@ -74,15 +454,40 @@ impl Material {
             }
             Material::Dielectric {
                 index_of_refraction,
+                absorption,
+                film,
             } => {
                 let mut rng = rand::thread_rng();
 
-                let attenuation = DVec3::splat(1.0);
+                // leaving through a back face means the ray
+                // crossed the inside of the glass to get here
+                let mut attenuation = if hit_record.front_face {
+                    DVec3::splat(1.0)
+                } else {
+                    let distance =
+                        hit_record.t * r_in.direction.length();
+                    (-*absorption * distance).exp()
+                };
+                // the hero wavelength picks the way through
+                // dispersive glass, and the others can't follow
+                let (index, mut wavelengths) = match r_in.wavelengths {
+                    Some(wavelengths)
+                        if index_of_refraction.is_dispersive() =>
+                    {
+                        (
+                            index_of_refraction.at(wavelengths.hero()),
+                            Some(wavelengths.hero_only()),
+                        )
+                    }
+                    wavelengths => {
+                        (index_of_refraction.nominal(), wavelengths)
+                    }
+                };
                 let refraction_ratio: f64 =
                     if hit_record.front_face {
-                        index_of_refraction.recip()
+                        index.recip()
                     } else {
-                        *index_of_refraction
+                        index
                     };
 
                 let unit_direction =
@@ -97,12 +502,48 @@ impl Material {
                 let cannot_refract =
                     refraction_ratio * sin_theta > 1.0;
 
-                let direction = if cannot_refract
-                    || reflectance(
-                        cos_theta,
-                        refraction_ratio,
-                    ) > rng.gen::<f64>()
-                {
+                let reflected = if cannot_refract {
+                    true
+                } else if let Some(film) = film {
+                    let (outer_index, inner_index) =
+                        if hit_record.front_face {
+                            (1., index)
+                        } else {
+                            (index, 1.)
+                        };
+                    let (film_reflectance, film_wavelengths) =
+                        ThinFilm::reflectance(
+                            wavelengths,
+                            |wavelength| {
+                                DVec3::splat(thin_film_dielectric(
+                                    cos_theta,
+                                    outer_index,
+                                    film.index_of_refraction,
+                                    film.thickness,
+                                    inner_index,
+                                    wavelength,
+                                ))
+                            },
+                        );
+                    wavelengths = film_wavelengths;
+
+                    // choose by the average and let the weight
+                    // carry the color
+                    let chance = film_reflectance
+                        .dot(DVec3::splat(1. / 3.));
+                    if rng.gen::<f64>() < chance {
+                        attenuation *= film_reflectance / chance;
+                        true
+                    } else {
+                        attenuation *= (DVec3::ONE - film_reflectance)
+                            / (1. - chance);
+                        false
+                    }
+                } else {
+                    reflectance(cos_theta, refraction_ratio)
+                        > rng.gen::<f64>()
+                };
+                let direction = if reflected {
                     reflect(
                         unit_direction,
                         hit_record.normal,
@@ -120,9 +561,127 @@ impl Material {
                     scattered: Ray {
                         origin: hit_record.point,
                         direction: direction,
+                        wavelengths,
+                    },
+                    pdf: None,
+                })
+            }
+            Material::Emissive { .. } => None,
+            Material::Isotropic { albedo } => Some(Scattered {
+                attenuation: albedo.value(
+                    hit_record.u,
+                    hit_record.v,
+                    hit_record.point,
+                ),
+                scattered: Ray {
+                    origin: hit_record.point,
+                    direction: random_unit_vector(),
+                    wavelengths: r_in.wavelengths,
+                },
+                pdf: Some(ISOTROPIC_PDF),
+            }),
+            Material::HenyeyGreenstein {
+                albedo, asymmetry, ..
+            } => {
+                let forward = r_in.direction.normalize();
+                let direction =
+                    phase::sample_henyey_greenstein(forward, *asymmetry);
+                // sampled exactly, so only the albedo is left
+                Some(Scattered {
+                    attenuation: albedo.value(
+                        hit_record.u,
+                        hit_record.v,
+                        hit_record.point,
+                    ),
+                    scattered: Ray {
+                        origin: hit_record.point,
+                        direction,
+                        wavelengths: r_in.wavelengths,
                     },
+                    pdf: Some(phase::henyey_greenstein(
+                        forward.dot(direction),
+                        *asymmetry,
+                    )),
+                })
+            }
+            Material::Conductor { ior, roughness } => {
+                let sample = microfacet::conductor_sample(
+                    ior,
+                    *roughness,
+                    hit_record.normal,
+                    -r_in.direction.normalize(),
+                )?;
+                Some(Scattered {
+                    attenuation: sample.weight,
+                    scattered: Ray {
+                        origin: hit_record.point,
+                        direction: sample.direction,
+                        wavelengths: r_in.wavelengths,
+                    },
+                    pdf: sample.pdf,
+                })
+            }
+            Material::RoughDielectric {
+                index_of_refraction,
+                roughness,
+            } => {
+                let sample = microfacet::dielectric_sample(
+                    relative_index(&hit_record, *index_of_refraction),
+                    *roughness,
+                    hit_record.normal,
+                    -r_in.direction.normalize(),
+                )?;
+                Some(Scattered {
+                    attenuation: sample.weight,
+                    scattered: Ray {
+                        origin: hit_record.point,
+                        direction: sample.direction,
+                        wavelengths: r_in.wavelengths,
+                    },
+                    pdf: sample.pdf,
+                })
+            }
+            Material::Principled(principled) => {
+                let sample = principled.sample(
+                    principled.base_color.value(
+                        hit_record.u,
+                        hit_record.v,
+                        hit_record.point,
+                    ),
+                    relative_index(
+                        &hit_record,
+                        principled.index_of_refraction,
+                    ),
+                    hit_record.normal,
+                    -r_in.direction.normalize(),
+                )?;
+                Some(Scattered {
+                    attenuation: sample.weight,
+                    scattered: Ray {
+                        origin: hit_record.point,
+                        direction: sample.direction,
+                        wavelengths: r_in.wavelengths,
+                    },
+                    pdf: sample.pdf,
                 })
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Index of refraction on the far side of the surface over the
+/// one on the side the ray arrives from
+fn relative_index(hit_record: &HitRecord, index_of_refraction: f64) -> f64 {
+    if hit_record.front_face {
+        index_of_refraction
+    } else {
+        index_of_refraction.recip()
+    }
+}
+
+/// Absorption coefficient that filters white light down to
+/// `color` over `distance`
+fn absorption(color: DVec3, distance: f64) -> DVec3 {
+    let color = color.clamp(DVec3::splat(1e-6), DVec3::ONE);
+    -DVec3::new(color.x.ln(), color.y.ln(), color.z.ln()) / distance
+}
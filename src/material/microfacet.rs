@@ -0,0 +1,398 @@
+//! GGX (Trowbridge-Reitz) microfacet scattering, after Walter
+//! et al., "Microfacet Models for Refraction through Rough
+//! Surfaces" (2007), with visible normal sampling from Heitz,
+//! "Sampling the GGX Distribution of Visible Normals" (2018).
+//!
+//! Directions are unit vectors pointing away from the surface:
+//! `wo` towards where the light goes (the viewer), `wi`
+//! towards where it comes from. `normal` is the shading normal
+//! on the side of `wo`.
+use std::f64::consts::PI;
+
+use glam::DVec3;
+use rand::Rng;
+
+use super::{
+    reflections::{fresnel_conductor, fresnel_dielectric, reflect},
+    ComplexIor,
+};
+
+/// Below this alpha the lobe is too narrow to evaluate and the
+/// surface is treated as a perfect mirror
+pub(super) const SMOOTH_ALPHA: f64 = 1e-3;
+
+/// Outcome of sampling a microfacet BSDF
+pub struct Sample {
+    pub direction: DVec3,
+    /// BSDF times cosine over the density
+    pub weight: DVec3,
+    /// `None` for the mirror-like limit
+    pub pdf: Option<f64>,
+}
+
+/// Artists' roughness in `[0, 1]` to the GGX width; squaring
+/// makes the slider feel linear.
+pub fn alpha(roughness: f64) -> f64 {
+    let roughness = roughness.clamp(0., 1.);
+    roughness * roughness
+}
+
+/// Orthonormal basis with the shading normal as z
+pub(super) struct Frame {
+    tangent: DVec3,
+    bitangent: DVec3,
+    pub(super) normal: DVec3,
+}
+
+impl Frame {
+    pub(super) fn new(normal: DVec3) -> Self {
+        let (tangent, bitangent) = normal.any_orthonormal_pair();
+        Frame {
+            tangent,
+            bitangent,
+            normal,
+        }
+    }
+    pub(super) fn to_local(&self, v: DVec3) -> DVec3 {
+        DVec3::new(
+            v.dot(self.tangent),
+            v.dot(self.bitangent),
+            v.dot(self.normal),
+        )
+    }
+    pub(super) fn to_world(&self, v: DVec3) -> DVec3 {
+        v.x * self.tangent + v.y * self.bitangent + v.z * self.normal
+    }
+}
+
+/// Isotropic GGX distribution of microfacet normals, in the
+/// local frame where the macro normal is +z
+pub(super) struct Ggx {
+    pub(super) alpha: f64,
+}
+
+impl Ggx {
+    /// Density of microfacet normals `h`, per unit projected area
+    pub(super) fn d(&self, h: DVec3) -> f64 {
+        if h.z <= 0. {
+            return 0.;
+        }
+        let a2 = self.alpha * self.alpha;
+        let t = h.z * h.z * (a2 - 1.) + 1.;
+        a2 / (PI * t * t)
+    }
+
+    /// Smith's auxiliary function for the fraction of
+    /// microfacets hidden when looking along `w`
+    fn lambda(&self, w: DVec3) -> f64 {
+        let cos2 = w.z * w.z;
+        if cos2 <= 0. {
+            return f64::INFINITY;
+        }
+        let tan2 = (1. - cos2).max(0.) / cos2;
+        0.5 * ((1. + self.alpha * self.alpha * tan2).sqrt() - 1.)
+    }
+
+    /// Masking: fraction of microfacets visible from `w`
+    pub(super) fn g1(&self, w: DVec3) -> f64 {
+        1. / (1. + self.lambda(w))
+    }
+
+    /// Height-correlated masking-shadowing for the pair; `wi`
+    /// may be below the surface
+    pub(super) fn g2(&self, wo: DVec3, wi: DVec3) -> f64 {
+        1. / (1. + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density of visible normals from `wo`, per solid angle
+    /// of `h`
+    pub(super) fn visible_pdf(&self, wo: DVec3, h: DVec3) -> f64 {
+        if wo.z <= 0. {
+            return 0.;
+        }
+        self.g1(wo) * wo.dot(h).max(0.) * self.d(h) / wo.z
+    }
+
+    /// Microfacet normal drawn in proportion to how much of
+    /// it `wo` sees
+    pub(super) fn sample_visible(&self, wo: DVec3) -> DVec3 {
+        let mut rng = rand::thread_rng();
+        let (u1, u2) = (rng.gen::<f64>(), rng.gen::<f64>());
+
+        // stretch to the hemisphere configuration
+        let vh = DVec3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z)
+            .normalize();
+        let length_squared = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length_squared > 0. {
+            DVec3::new(-vh.y, vh.x, 0.) / length_squared.sqrt()
+        } else {
+            DVec3::X
+        };
+        let t2 = vh.cross(t1);
+
+        // point on the projected half disk
+        let r = u1.sqrt();
+        let phi = 2. * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1. + vh.z);
+        let p2 = (1. - s) * (1. - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1
+            + p2 * t2
+            + (1. - p1 * p1 - p2 * p2).max(0.).sqrt() * vh;
+
+        // and back to the ellipsoid
+        DVec3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(0.))
+            .normalize()
+    }
+}
+
+/// Rough metal: BSDF times cosine for light from `wi`.
+pub fn conductor_eval(
+    ior: &ComplexIor,
+    roughness: f64,
+    normal: DVec3,
+    wo: DVec3,
+    wi: DVec3,
+) -> DVec3 {
+    let ggx = Ggx {
+        alpha: alpha(roughness),
+    };
+    if ggx.alpha < SMOOTH_ALPHA {
+        return DVec3::ZERO;
+    }
+    let frame = Frame::new(normal);
+    let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+    if wo.z <= 0. || wi.z <= 0. {
+        return DVec3::ZERO;
+    }
+
+    let h = (wo + wi).normalize();
+    let fresnel = fresnel_conductor(wi.dot(h), ior.eta, ior.k);
+    fresnel * ggx.d(h) * ggx.g2(wo, wi) / (4. * wo.z)
+}
+
+/// Density with which `conductor_sample` picks `wi`.
+pub fn conductor_pdf(
+    roughness: f64,
+    normal: DVec3,
+    wo: DVec3,
+    wi: DVec3,
+) -> f64 {
+    let ggx = Ggx {
+        alpha: alpha(roughness),
+    };
+    if ggx.alpha < SMOOTH_ALPHA {
+        return 0.;
+    }
+    let frame = Frame::new(normal);
+    let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+    if wo.z <= 0. || wi.z <= 0. {
+        return 0.;
+    }
+
+    let h = (wo + wi).normalize();
+    ggx.visible_pdf(wo, h) / (4. * wo.dot(h))
+}
+
+/// Reflects `wo` about a sampled visible microfacet normal.
+pub fn conductor_sample(
+    ior: &ComplexIor,
+    roughness: f64,
+    normal: DVec3,
+    wo: DVec3,
+) -> Option<Sample> {
+    let ggx = Ggx {
+        alpha: alpha(roughness),
+    };
+    let frame = Frame::new(normal);
+    let wo_local = frame.to_local(wo);
+    if wo_local.z <= 0. {
+        return None;
+    }
+
+    if ggx.alpha < SMOOTH_ALPHA {
+        return Some(Sample {
+            direction: reflect(-wo, normal),
+            weight: fresnel_conductor(wo_local.z, ior.eta, ior.k),
+            pdf: None,
+        });
+    }
+
+    let h = ggx.sample_visible(wo_local);
+    let wi = reflect(-wo_local, h);
+    if wi.z <= 0. {
+        return None;
+    }
+
+    // D and G1 cancel against the density of visible normals
+    let fresnel = fresnel_conductor(wi.dot(h), ior.eta, ior.k);
+    Some(Sample {
+        direction: frame.to_world(wi),
+        weight: fresnel * ggx.g2(wo_local, wi) / ggx.g1(wo_local),
+        pdf: Some(
+            ggx.visible_pdf(wo_local, h) / (4. * wo_local.dot(h)),
+        ),
+    })
+}
+
+/// Microfacet normal that turns `wo` into `wi`, facing `wo`'s
+/// side, and whether `wi` is a reflection. `eta` is the index
+/// on the far side over the index on `wo`'s side.
+fn dielectric_half_vector(
+    eta: f64,
+    wo: DVec3,
+    wi: DVec3,
+) -> Option<(DVec3, bool)> {
+    let reflected = wi.z > 0.;
+    let h = if reflected { wo + wi } else { wo + eta * wi };
+    let h = h.try_normalize()?;
+    let h = if h.z < 0. { -h } else { h };
+
+    // both directions have to be on the correct sides of
+    // the microfacet
+    let valid = wo.dot(h) > 0.
+        && if reflected {
+            wi.dot(h) > 0.
+        } else {
+            wi.dot(h) < 0.
+        };
+    valid.then_some((h, reflected))
+}
+
+/// Frosted glass: BSDF times cosine for light from `wi`, which
+/// may be on either side of the surface.
+pub fn dielectric_eval(
+    eta: f64,
+    roughness: f64,
+    normal: DVec3,
+    wo: DVec3,
+    wi: DVec3,
+) -> f64 {
+    let ggx = Ggx {
+        alpha: alpha(roughness),
+    };
+    if ggx.alpha < SMOOTH_ALPHA {
+        return 0.;
+    }
+    let frame = Frame::new(normal);
+    let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+    if wo.z <= 0. || wi.z == 0. {
+        return 0.;
+    }
+    let Some((h, reflected)) = dielectric_half_vector(eta, wo, wi)
+    else {
+        return 0.;
+    };
+
+    let fresnel = fresnel_dielectric(wo.dot(h), eta);
+    let g2 = ggx.g2(wo, wi);
+    if reflected {
+        fresnel * ggx.d(h) * g2 / (4. * wo.z)
+    } else {
+        let denominator = wo.dot(h) + eta * wi.dot(h);
+        (1. - fresnel)
+            * ggx.d(h)
+            * g2
+            * eta
+            * eta
+            * wo.dot(h)
+            * wi.dot(h).abs()
+            / (wo.z * denominator * denominator)
+    }
+}
+
+/// Density with which `dielectric_sample` picks `wi`.
+pub fn dielectric_pdf(
+    eta: f64,
+    roughness: f64,
+    normal: DVec3,
+    wo: DVec3,
+    wi: DVec3,
+) -> f64 {
+    let ggx = Ggx {
+        alpha: alpha(roughness),
+    };
+    if ggx.alpha < SMOOTH_ALPHA {
+        return 0.;
+    }
+    let frame = Frame::new(normal);
+    let (wo, wi) = (frame.to_local(wo), frame.to_local(wi));
+    if wo.z <= 0. || wi.z == 0. {
+        return 0.;
+    }
+    let Some((h, reflected)) = dielectric_half_vector(eta, wo, wi)
+    else {
+        return 0.;
+    };
+
+    let fresnel = fresnel_dielectric(wo.dot(h), eta);
+    let visible = ggx.visible_pdf(wo, h);
+    if reflected {
+        fresnel * visible / (4. * wo.dot(h))
+    } else {
+        let denominator = wo.dot(h) + eta * wi.dot(h);
+        (1. - fresnel) * visible * eta * eta * wi.dot(h).abs()
+            / (denominator * denominator)
+    }
+}
+
+/// Reflects or refracts `wo` through a sampled visible
+/// microfacet, choosing by the Fresnel reflectance.
+pub fn dielectric_sample(
+    eta: f64,
+    roughness: f64,
+    normal: DVec3,
+    wo: DVec3,
+) -> Option<Sample> {
+    let ggx = Ggx {
+        alpha: alpha(roughness),
+    };
+    let frame = Frame::new(normal);
+    let wo_local = frame.to_local(wo);
+    if wo_local.z <= 0. {
+        return None;
+    }
+
+    let smooth = ggx.alpha < SMOOTH_ALPHA;
+    let h = if smooth {
+        DVec3::Z
+    } else {
+        ggx.sample_visible(wo_local)
+    };
+
+    let cos_o = wo_local.dot(h);
+    let fresnel = fresnel_dielectric(cos_o, eta);
+    let reflected = rand::thread_rng().gen::<f64>() < fresnel;
+    let wi = if reflected {
+        reflect(-wo_local, h)
+    } else {
+        // Snell's law about the microfacet normal
+        let sin2_t = (1. - cos_o * cos_o) / (eta * eta);
+        let cos_t = (1. - sin2_t).max(0.).sqrt();
+        -wo_local / eta + (cos_o / eta - cos_t) * h
+    };
+
+    if smooth {
+        return Some(Sample {
+            direction: frame.to_world(wi),
+            weight: DVec3::ONE,
+            pdf: None,
+        });
+    }
+    // reflection or refraction onto the wrong side of the
+    // macro surface
+    if reflected != (wi.z > 0.) {
+        return None;
+    }
+
+    // the Fresnel choice cancels F, and D and G1 cancel
+    // against the density of visible normals
+    let direction = frame.to_world(wi);
+    Some(Sample {
+        direction,
+        weight: DVec3::splat(ggx.g2(wo_local, wi) / ggx.g1(wo_local)),
+        pdf: Some(dielectric_pdf(
+            eta, roughness, normal, wo, direction,
+        )),
+    })
+}
@@ -0,0 +1,43 @@
+//! Phase functions: how much of the light travelling through a
+//! medium scatters into each direction at a collision.
+use std::f64::consts::PI;
+
+use glam::DVec3;
+use rand::Rng;
+
+/// Below this the Henyey-Greenstein lobe is isotropic
+const ISOTROPIC_ASYMMETRY: f64 = 1e-3;
+
+/// Henyey-Greenstein density of turning by an angle with
+/// cosine `cos_theta`, where 1 means carrying straight on.
+/// `asymmetry` runs from -1 (back towards the light) through 0
+/// (evenly everywhere) to 1 (forward, as in clouds).
+pub fn henyey_greenstein(cos_theta: f64, asymmetry: f64) -> f64 {
+    let g = asymmetry;
+    let denominator = 1. + g * g - 2. * g * cos_theta;
+    (1. - g * g) / (4. * PI * denominator * denominator.sqrt())
+}
+
+/// Direction drawn in proportion to `henyey_greenstein` for
+/// light travelling along the unit vector `direction`.
+pub fn sample_henyey_greenstein(
+    direction: DVec3,
+    asymmetry: f64,
+) -> DVec3 {
+    let mut rng = rand::thread_rng();
+    let (u1, u2) = (rng.gen::<f64>(), rng.gen::<f64>());
+
+    let g = asymmetry;
+    let cos_theta = if g.abs() < ISOTROPIC_ASYMMETRY {
+        1. - 2. * u1
+    } else {
+        let s = (1. - g * g) / (1. - g + 2. * g * u1);
+        ((1. + g * g - s * s) / (2. * g)).clamp(-1., 1.)
+    };
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = 2. * PI * u2;
+
+    let (tangent, bitangent) = direction.any_orthonormal_pair();
+    sin_theta * (phi.cos() * tangent + phi.sin() * bitangent)
+        + cos_theta * direction
+}
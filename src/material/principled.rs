@@ -0,0 +1,342 @@
+//! An all-in-one surface after Burley, "Physically Based
+//! Shading at Disney" (2012), with the parameters of Blender's
+//! Principled BSDF: a diffuse base with sheen, a GGX specular
+//! layer that turns into metal, a rough glass layer and a
+//! clear coat on top.
+use std::f64::consts::FRAC_1_PI;
+
+use glam::DVec3;
+use rand::Rng;
+
+use super::{
+    microfacet::{self, alpha, Frame, Ggx, Sample},
+    reflections::reflect,
+};
+use crate::{spectrum::luminance, texture::Texture};
+
+/// Smoother lobes are too narrow to evaluate, so roughness is
+/// kept just above the point where GGX turns into a mirror
+const MIN_ROUGHNESS: f64 = 0.035;
+
+/// Parameters of `Material::Principled`. All weights run from
+/// 0 to 1; start from `Default` and set the ones you need.
+#[derive(Debug, Clone)]
+pub struct Principled {
+    /// Diffuse color, or the reflectance of a metal
+    pub base_color: Texture,
+    /// Blends from dielectric to metal
+    pub metallic: f64,
+    /// From mirror-like to fully matte highlights
+    pub roughness: f64,
+    /// Reflectance of dielectrics at normal incidence, where
+    /// the default 0.5 means the 4% of most plastics
+    pub specular: f64,
+    /// Strength of a colorless glossy layer on top
+    pub clearcoat: f64,
+    pub clearcoat_roughness: f64,
+    /// Soft rim of light at grazing angles, for cloth
+    pub sheen: f64,
+    /// Blends the sheen from white to the base color
+    pub sheen_tint: f64,
+    /// Blends from opaque to glass tinted by the base color
+    pub transmission: f64,
+    /// Index of refraction of the glass layer
+    pub index_of_refraction: f64,
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        Principled {
+            base_color: DVec3::splat(0.8).into(),
+            metallic: 0.,
+            roughness: 0.5,
+            specular: 0.5,
+            clearcoat: 0.,
+            clearcoat_roughness: 0.03,
+            sheen: 0.,
+            sheen_tint: 0.5,
+            transmission: 0.,
+            index_of_refraction: 1.45,
+        }
+    }
+}
+
+/// Parameters resolved at one hit point
+struct Layers {
+    frame: Frame,
+    base_color: DVec3,
+    /// Relative index of refraction for the glass layer
+    eta: f64,
+    roughness: f64,
+    specular: Ggx,
+    clearcoat: Ggx,
+    /// Reflectance of the specular layer at normal incidence
+    f0: DVec3,
+    /// Weights of the diffuse, specular and glass layers
+    diffuse_weight: f64,
+    specular_weight: f64,
+    glass_weight: f64,
+    /// Light that makes it through the clear coat
+    coat_transmittance: f64,
+    /// Chances of sampling the diffuse, specular, glass and
+    /// clear coat lobes, adding up to one
+    probabilities: [f64; 4],
+}
+
+impl Principled {
+    fn layers(
+        &self,
+        base_color: DVec3,
+        eta: f64,
+        normal: DVec3,
+        wo: DVec3,
+    ) -> Layers {
+        let frame = Frame::new(normal);
+        let cos_o = frame.to_local(wo).z;
+
+        let metallic = self.metallic.clamp(0., 1.);
+        let transmission = self.transmission.clamp(0., 1.);
+        let clearcoat = self.clearcoat.clamp(0., 1.);
+        let roughness = self.roughness.max(MIN_ROUGHNESS);
+
+        let dielectric_f0 =
+            DVec3::splat(0.08 * self.specular.clamp(0., 1.));
+        let f0 = dielectric_f0.lerp(base_color, metallic);
+
+        let diffuse_weight = (1. - metallic) * (1. - transmission);
+        let glass_weight = (1. - metallic) * transmission;
+        let specular_weight = 1. - glass_weight;
+        let coat_reflectance =
+            clearcoat * schlick(DVec3::splat(0.04), cos_o).x;
+
+        let probabilities = [
+            diffuse_weight,
+            specular_weight * luminance(schlick(f0, cos_o)),
+            glass_weight,
+            coat_reflectance,
+        ];
+        let total: f64 = probabilities.iter().sum();
+        let probabilities = if total > 0. {
+            probabilities.map(|p| p / total)
+        } else {
+            [0.; 4]
+        };
+
+        Layers {
+            frame,
+            base_color,
+            eta,
+            roughness,
+            specular: Ggx {
+                alpha: alpha(roughness),
+            },
+            clearcoat: Ggx {
+                alpha: alpha(
+                    self.clearcoat_roughness.max(MIN_ROUGHNESS),
+                ),
+            },
+            f0,
+            diffuse_weight,
+            specular_weight,
+            glass_weight,
+            coat_transmittance: 1. - coat_reflectance,
+            probabilities,
+        }
+    }
+
+    /// Sheen color at full strength
+    fn sheen_color(&self, base_color: DVec3) -> DVec3 {
+        let tint = base_color / luminance(base_color).max(1e-6);
+        self.sheen
+            * DVec3::ONE.lerp(tint, self.sheen_tint.clamp(0., 1.))
+    }
+
+    /// BSDF times cosine for light from `wi`, see
+    /// `Material::eval`. `eta` is the relative index of
+    /// refraction across the surface.
+    pub fn eval(
+        &self,
+        base_color: DVec3,
+        eta: f64,
+        normal: DVec3,
+        wo: DVec3,
+        wi: DVec3,
+    ) -> DVec3 {
+        let layers = self.layers(base_color, eta, normal, wo);
+        self.eval_layers(&layers, wo, wi)
+    }
+
+    /// Density with which `sample` picks `wi`.
+    pub fn pdf(
+        &self,
+        base_color: DVec3,
+        eta: f64,
+        normal: DVec3,
+        wo: DVec3,
+        wi: DVec3,
+    ) -> f64 {
+        let layers = self.layers(base_color, eta, normal, wo);
+        pdf_layers(&layers, wo, wi)
+    }
+
+    /// Picks one lobe in proportion to its expected
+    /// contribution and samples a direction from it, weighted
+    /// against the density of all lobes together.
+    pub fn sample(
+        &self,
+        base_color: DVec3,
+        eta: f64,
+        normal: DVec3,
+        wo: DVec3,
+    ) -> Option<Sample> {
+        let layers = self.layers(base_color, eta, normal, wo);
+        let frame = &layers.frame;
+        let wo_local = frame.to_local(wo);
+        if wo_local.z <= 0. {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+        let choice = rng.gen::<f64>();
+        let [diffuse, specular, glass, _] = layers.probabilities;
+        let wi = if choice < diffuse {
+            frame.to_world(cosine_direction(&mut rng))
+        } else if choice < diffuse + specular {
+            let h = layers.specular.sample_visible(wo_local);
+            frame.to_world(reflect(-wo_local, h))
+        } else if choice < diffuse + specular + glass {
+            microfacet::dielectric_sample(
+                layers.eta,
+                layers.roughness,
+                normal,
+                wo,
+            )?
+            .direction
+        } else {
+            let h = layers.clearcoat.sample_visible(wo_local);
+            frame.to_world(reflect(-wo_local, h))
+        };
+
+        let pdf = pdf_layers(&layers, wo, wi);
+        if pdf <= 0. {
+            return None;
+        }
+        Some(Sample {
+            direction: wi,
+            weight: self.eval_layers(&layers, wo, wi) / pdf,
+            pdf: Some(pdf),
+        })
+    }
+
+    fn eval_layers(
+        &self,
+        layers: &Layers,
+        wo: DVec3,
+        wi: DVec3,
+    ) -> DVec3 {
+        let frame = &layers.frame;
+        let (wo_local, wi_local) =
+            (frame.to_local(wo), frame.to_local(wi));
+        if wo_local.z <= 0. {
+            return DVec3::ZERO;
+        }
+
+        let mut base = DVec3::ZERO;
+        let mut coat = DVec3::ZERO;
+        if wi_local.z > 0. {
+            let h = (wo_local + wi_local).normalize();
+            let cos_d = wi_local.dot(h);
+
+            let sheen = self.sheen_color(layers.base_color)
+                * schlick_weight(cos_d);
+            base += layers.diffuse_weight
+                * (layers.base_color * FRAC_1_PI + sheen)
+                * wi_local.z;
+
+            let ggx = &layers.specular;
+            base += layers.specular_weight
+                * schlick(layers.f0, cos_d)
+                * ggx.d(h)
+                * ggx.g2(wo_local, wi_local)
+                / (4. * wo_local.z);
+
+            let ggx = &layers.clearcoat;
+            coat = DVec3::splat(
+                self.clearcoat.clamp(0., 1.)
+                    * schlick(DVec3::splat(0.04), cos_d).x
+                    * ggx.d(h)
+                    * ggx.g2(wo_local, wi_local)
+                    / (4. * wo_local.z),
+            );
+        }
+
+        if layers.glass_weight > 0. {
+            let glass = microfacet::dielectric_eval(
+                layers.eta,
+                layers.roughness,
+                frame.normal,
+                wo,
+                wi,
+            );
+            // only light that went through the glass is tinted
+            let tint = if wi_local.z < 0. {
+                layers.base_color
+            } else {
+                DVec3::ONE
+            };
+            base += layers.glass_weight * glass * tint;
+        }
+
+        layers.coat_transmittance * base + coat
+    }
+}
+
+fn pdf_layers(layers: &Layers, wo: DVec3, wi: DVec3) -> f64 {
+    let frame = &layers.frame;
+    let (wo_local, wi_local) = (frame.to_local(wo), frame.to_local(wi));
+    if wo_local.z <= 0. {
+        return 0.;
+    }
+    let [diffuse, specular, glass, clearcoat] = layers.probabilities;
+
+    let mut pdf = 0.;
+    if wi_local.z > 0. {
+        let h = (wo_local + wi_local).normalize();
+        pdf += diffuse * wi_local.z * FRAC_1_PI;
+        pdf += specular * layers.specular.visible_pdf(wo_local, h)
+            / (4. * wo_local.dot(h));
+        pdf += clearcoat * layers.clearcoat.visible_pdf(wo_local, h)
+            / (4. * wo_local.dot(h));
+    }
+    if glass > 0. {
+        pdf += glass
+            * microfacet::dielectric_pdf(
+                layers.eta,
+                layers.roughness,
+                frame.normal,
+                wo,
+                wi,
+            );
+    }
+    pdf
+}
+
+/// Schlick's approximation of the Fresnel reflectance
+fn schlick(f0: DVec3, cosine: f64) -> DVec3 {
+    f0.lerp(DVec3::ONE, schlick_weight(cosine))
+}
+
+fn schlick_weight(cosine: f64) -> f64 {
+    (1. - cosine.clamp(0., 1.)).powi(5)
+}
+
+/// Cosine distributed direction about +z
+fn cosine_direction(rng: &mut impl Rng) -> DVec3 {
+    let r = rng.gen::<f64>().sqrt();
+    let phi = 2. * std::f64::consts::PI * rng.gen::<f64>();
+    DVec3::new(
+        r * phi.cos(),
+        r * phi.sin(),
+        (1. - r * r).max(0.).sqrt(),
+    )
+}
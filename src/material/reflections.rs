@@ -22,4 +22,148 @@ pub fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
     let mut r0 = (1. - ref_idx) / (1. + ref_idx);
     r0 = r0 * r0;
     return r0 + (1. - r0) * (1. - cosine).powf(5.);
-}
\ No newline at end of file
+}
+
+/// Exact Fresnel reflectance of unpolarized light at a
+/// dielectric boundary. `eta` is the index on the far side over
+/// the index on the incident side.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let cos_i = cos_i.clamp(0., 1.);
+    let sin2_t = (1. - cos_i * cos_i) / (eta * eta);
+    // total internal reflection
+    if sin2_t >= 1. {
+        return 1.;
+    }
+    let cos_t = (1. - sin2_t).sqrt();
+
+    let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    0.5 * (rs * rs + rp * rp)
+}
+
+/// Exact Fresnel reflectance of a metal with complex index of
+/// refraction `eta + i k`, per color channel.
+pub fn fresnel_conductor(cos_i: f64, eta: DVec3, k: DVec3) -> DVec3 {
+    let cos2 = cos_i.clamp(0., 1.).powi(2);
+    let sin2 = 1. - cos2;
+
+    let t0 = eta * eta - k * k - DVec3::splat(sin2);
+    let a2_plus_b2 = (t0 * t0 + 4. * eta * eta * k * k).powf(0.5);
+    let a = (0.5 * (a2_plus_b2 + t0)).max(DVec3::ZERO).powf(0.5);
+
+    let t1 = a2_plus_b2 + DVec3::splat(cos2);
+    let t2 = 2. * cos_i.clamp(0., 1.) * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + DVec3::splat(sin2 * sin2);
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rp + rs)
+}
+
+/// Reflectance of a thin film of index `film_index` and
+/// `thickness` nanometers on a dielectric of index
+/// `substrate_index`, seen from a medium of index
+/// `outer_index`, for light of `wavelength` nanometers. Light
+/// bouncing inside the film interferes with itself, so the
+/// result oscillates with wavelength and angle (Airy's
+/// formula).
+pub fn thin_film_dielectric(
+    cos_i: f64,
+    outer_index: f64,
+    film_index: f64,
+    thickness: f64,
+    substrate_index: f64,
+    wavelength: f64,
+) -> f64 {
+    let cos_i = cos_i.clamp(0., 1.);
+    let sin2_i = 1. - cos_i * cos_i;
+    let sin2_substrate =
+        sin2_i * (outer_index / substrate_index).powi(2);
+    // light that can't leave through the substrate ends up back
+    // outside, however much it bounces within the film
+    if sin2_substrate >= 1. {
+        return 1.;
+    }
+    let cos_substrate = (1. - sin2_substrate).sqrt();
+    let Some(cos_film) = film_cosine(sin2_i, outer_index, film_index)
+    else {
+        return 1.;
+    };
+
+    let (s12, p12) =
+        amplitudes(outer_index, cos_i, film_index, cos_film);
+    let (s23, p23) = amplitudes(
+        film_index,
+        cos_film,
+        substrate_index,
+        cos_substrate,
+    );
+    let phase = film_phase(film_index, thickness, cos_film, wavelength);
+    0.5 * (airy(s12, s23, phase) + airy(p12, p23, phase))
+}
+
+/// Like `thin_film_dielectric`, for a film on a mirror that
+/// reflects `substrate_reflectance` of the light on its own.
+pub fn thin_film_mirror(
+    cos_i: f64,
+    outer_index: f64,
+    film_index: f64,
+    thickness: f64,
+    substrate_reflectance: f64,
+    wavelength: f64,
+) -> f64 {
+    let cos_i = cos_i.clamp(0., 1.);
+    let Some(cos_film) =
+        film_cosine(1. - cos_i * cos_i, outer_index, film_index)
+    else {
+        return 1.;
+    };
+
+    let (s12, p12) =
+        amplitudes(outer_index, cos_i, film_index, cos_film);
+    // an ideal conductor flips the phase of either polarization
+    let r23 = -substrate_reflectance.clamp(0., 1.).sqrt();
+    let phase = film_phase(film_index, thickness, cos_film, wavelength);
+    0.5 * (airy(s12, r23, phase) + airy(p12, r23, phase))
+}
+
+/// Cosine of the angle inside the film, `None` if light is
+/// totally reflected before entering it
+fn film_cosine(
+    sin2_i: f64,
+    outer_index: f64,
+    film_index: f64,
+) -> Option<f64> {
+    let sin2_film = sin2_i * (outer_index / film_index).powi(2);
+    (sin2_film < 1.).then(|| (1. - sin2_film).sqrt())
+}
+
+/// Fresnel amplitude coefficients for s and p polarized light
+/// going from index `n1` into `n2`
+fn amplitudes(n1: f64, cos1: f64, n2: f64, cos2: f64) -> (f64, f64) {
+    let s = (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2);
+    let p = (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2);
+    (s, p)
+}
+
+/// Phase difference between light reflected off the top and
+/// the bottom of the film
+fn film_phase(
+    film_index: f64,
+    thickness: f64,
+    cos_film: f64,
+    wavelength: f64,
+) -> f64 {
+    4. * std::f64::consts::PI * film_index * thickness * cos_film
+        / wavelength
+}
+
+/// Reflectance of one polarization from the amplitude
+/// coefficients of the film's two interfaces
+fn airy(r12: f64, r23: f64, phase: f64) -> f64 {
+    let cross = 2. * r12 * r23 * phase.cos();
+    (r12 * r12 + r23 * r23 + cross)
+        / (1. + r12 * r12 * r23 * r23 + cross)
+}
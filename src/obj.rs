@@ -0,0 +1,544 @@
+//! Wavefront OBJ/MTL import.
+//!
+//! Reads `v`/`vn`/`vt`/`f` records into one `TriangleMesh` per
+//! material. Polygons are fan-triangulated and negative
+//! (relative) indices are resolved. `.mtl` materials map onto
+//! the existing `Material` variants, taking the first rule that
+//! applies:
+//!
+//! - `Ke` other than black: `Emissive` with `Ke` as the color
+//! - any of the PBR keys `Pr`, `Pm`, `Ps`, `Pc` or `Pcr`:
+//!   `Principled` with roughness, metallic, sheen, clearcoat
+//!   and clearcoat roughness from them, `Kd` as the base color,
+//!   `Ni` as the index of refraction and 1 - `d` as the
+//!   transmission; `Ks`, `Ns` and `illum` are ignored
+//! - `d` < 1 (or `Tr` > 0): `Dielectric` with `Ni` as the index
+//!   of refraction and `Tf` as the color white light is tinted
+//!   to after one unit of distance inside
+//! - `Ks` brighter than `Kd`, or `illum 3`: `Metal` with `Ks` as
+//!   the albedo and a fuzz derived from `Ns`
+//! - anything else: `Lambertian` with `Kd` as the albedo
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use glam::{DVec2, DVec3};
+
+use crate::{material::{Material, Principled}, shapes::mesh::TriangleMesh, texture::Texture};
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => {
+                write!(f, "{}: {}", path.display(), source)
+            }
+            ObjError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl Error for ObjError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ObjError::Io { source, .. } => Some(source),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+/// Material for faces that come before any `usemtl`
+const DEFAULT_MATERIAL: Material = Material::Lambertian {
+    albedo: Texture::Solid(DVec3::new(0.5, 0.5, 0.5)),
+};
+
+/// Loads an `.obj` file and the `.mtl` libraries it references
+/// (resolved relative to the `.obj` file).
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<TriangleMesh>, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut positions: Vec<DVec3> = vec![];
+    let mut normals: Vec<DVec3> = vec![];
+    let mut uvs: Vec<DVec2> = vec![];
+    let mut materials: HashMap<String, Material> = HashMap::new();
+
+    // one mesh per material, in order of first use
+    let mut groups: Vec<MeshGroup> = vec![];
+    let mut group_by_name: HashMap<String, usize> = HashMap::new();
+    let mut current: Option<usize> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let mut parser = LineParser::new(path, index + 1, line);
+        let Some(keyword) = parser.next_token() else {
+            continue;
+        };
+
+        match keyword {
+            "v" => positions.push(parser.vec3()?),
+            "vn" => normals.push(parser.vec3()?),
+            "vt" => {
+                let u = parser.float()?;
+                // v is optional, as is a trailing w
+                let v = parser.optional_float()?.unwrap_or(0.);
+                uvs.push(DVec2::new(u, v));
+            }
+            "f" => {
+                let mut corners = vec![];
+                while let Some(token) = parser.next_token() {
+                    corners.push(parser.face_vertex(
+                        token,
+                        positions.len(),
+                        uvs.len(),
+                        normals.len(),
+                    )?);
+                }
+                if corners.len() < 3 {
+                    return Err(parser.error("face needs at least three vertices"));
+                }
+
+                let group = *current.get_or_insert_with(|| {
+                    groups.push(MeshGroup::new(DEFAULT_MATERIAL));
+                    groups.len() - 1
+                });
+                groups[group].add_polygon(&corners, &positions, &uvs, &normals);
+            }
+            "usemtl" => {
+                let name = parser.rest();
+                let material = materials.get(name).cloned().ok_or_else(|| {
+                    parser.error(&format!("undefined material `{name}`"))
+                })?;
+                let group = *group_by_name.entry(name.to_string()).or_insert_with(|| {
+                    groups.push(MeshGroup::new(material));
+                    groups.len() - 1
+                });
+                current = Some(group);
+            }
+            "mtllib" => {
+                for file in parser.rest().split_whitespace() {
+                    materials.extend(load_mtl(&base_dir.join(file))?);
+                }
+            }
+            // groups, objects, smoothing groups and anything
+            // we don't render
+            _ => {}
+        }
+    }
+
+    Ok(groups
+        .into_iter()
+        .filter(|group| !group.indices.is_empty())
+        .map(MeshGroup::build)
+        .collect())
+}
+
+/// Parses an `.mtl` library into materials by name.
+pub fn load_mtl(path: &Path) -> Result<HashMap<String, Material>, ObjError> {
+    let source = read(path)?;
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlEntry)> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let mut parser = LineParser::new(path, index + 1, line);
+        let Some(keyword) = parser.next_token() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            if let Some((name, entry)) = current.take() {
+                materials.insert(name, entry.to_material());
+            }
+            current = Some((parser.rest().to_string(), MtlEntry::default()));
+            continue;
+        }
+
+        let Some((_, entry)) = current.as_mut() else {
+            // statements before the first newmtl have nothing
+            // to apply to
+            continue;
+        };
+        match keyword {
+            "Kd" => entry.diffuse = parser.vec3()?,
+            "Ks" => entry.specular = parser.vec3()?,
+            "Tf" => entry.transmission_filter = parser.vec3()?,
+            "Ke" => entry.emission = parser.vec3()?,
+            "Ns" => entry.shininess = parser.float()?,
+            "Ni" => entry.index_of_refraction = parser.float()?,
+            "d" => entry.dissolve = parser.float()?,
+            "Tr" => entry.dissolve = 1. - parser.float()?,
+            "illum" => entry.illum = parser.float()? as u32,
+            // PBR extension written by Blender and most other exporters
+            "Pr" => entry.pbr().roughness = parser.float()?,
+            "Pm" => entry.pbr().metallic = parser.float()?,
+            "Ps" => entry.pbr().sheen = parser.float()?,
+            "Pc" => entry.pbr().clearcoat = parser.float()?,
+            "Pcr" => entry.pbr().clearcoat_roughness = parser.float()?,
+            _ => {}
+        }
+    }
+    if let Some((name, entry)) = current {
+        materials.insert(name, entry.to_material());
+    }
+
+    Ok(materials)
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+struct MtlEntry {
+    diffuse: DVec3,
+    specular: DVec3,
+    /// Color white light is filtered to after one unit of glass
+    transmission_filter: DVec3,
+    emission: DVec3,
+    shininess: f64,
+    index_of_refraction: f64,
+    dissolve: f64,
+    illum: u32,
+    /// Only set when the file uses the PBR extension
+    pbr: Option<Principled>,
+}
+
+impl Default for MtlEntry {
+    fn default() -> Self {
+        Self {
+            diffuse: DVec3::splat(0.8),
+            specular: DVec3::ZERO,
+            transmission_filter: DVec3::ONE,
+            emission: DVec3::ZERO,
+            shininess: 0.,
+            index_of_refraction: 1.5,
+            dissolve: 1.,
+            illum: 2,
+            pbr: None,
+        }
+    }
+}
+
+impl MtlEntry {
+    fn pbr(&mut self) -> &mut Principled {
+        self.pbr.get_or_insert_with(Principled::default)
+    }
+
+    fn to_material(&self) -> Material {
+        let transparent = self.dissolve < 1. || matches!(self.illum, 4 | 6 | 7 | 9);
+        let metallic = self.illum == 3
+            || self.specular.max_element() > self.diffuse.max_element();
+
+        if self.emission.max_element() > 0. {
+            Material::Emissive {
+                color: self.emission,
+                intensity: 1.,
+            }
+        } else if let Some(pbr) = &self.pbr {
+            // Kd, Ni and d keep their meaning, whichever order
+            // they came in
+            Material::Principled(Principled {
+                base_color: self.diffuse.into(),
+                index_of_refraction: self.index_of_refraction,
+                transmission: 1. - self.dissolve.clamp(0., 1.),
+                ..pbr.clone()
+            })
+        } else if transparent {
+            Material::tinted_glass(self.index_of_refraction, self.transmission_filter, 1.)
+        } else if metallic {
+            // Ns is a Phong exponent (0..1000); map it to a
+            // roughness the way Phong is usually fitted to Beckmann
+            let fuzz = (2. / (self.shininess.max(0.) + 2.)).sqrt();
+            Material::Metal {
+                albedo: self.specular.into(),
+                fuzz,
+                film: None,
+            }
+        } else {
+            Material::Lambertian {
+                albedo: self.diffuse.into(),
+            }
+        }
+    }
+}
+
+/// Resolved corner of a face: 0-based indices into the
+/// position, uv and normal lists
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct FaceVertex {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+/// Faces sharing one material, with the OBJ's separate
+/// attribute indices merged into a single index per vertex
+struct MeshGroup {
+    material: Material,
+    vertices: HashMap<FaceVertex, usize>,
+    positions: Vec<DVec3>,
+    normals: Vec<Option<DVec3>>,
+    uvs: Vec<Option<DVec2>>,
+    indices: Vec<[usize; 3]>,
+}
+
+impl MeshGroup {
+    fn new(material: Material) -> Self {
+        MeshGroup {
+            material,
+            vertices: HashMap::new(),
+            positions: vec![],
+            normals: vec![],
+            uvs: vec![],
+            indices: vec![],
+        }
+    }
+
+    fn add_polygon(
+        &mut self,
+        corners: &[FaceVertex],
+        positions: &[DVec3],
+        uvs: &[DVec2],
+        normals: &[DVec3],
+    ) {
+        let indices: Vec<usize> = corners
+            .iter()
+            .map(|corner| {
+                *self.vertices.entry(*corner).or_insert_with(|| {
+                    self.positions.push(positions[corner.position]);
+                    self.uvs.push(corner.uv.map(|i| uvs[i]));
+                    self.normals.push(corner.normal.map(|i| normals[i]));
+                    self.positions.len() - 1
+                })
+            })
+            .collect();
+
+        // fan around the first corner
+        for i in 1..indices.len() - 1 {
+            self.indices.push([indices[0], indices[i], indices[i + 1]]);
+        }
+    }
+
+    fn build(self) -> TriangleMesh {
+        // Vertices without a normal get the normal of a face
+        // that uses them, unless no vertex has one at all
+        let normals = if self.normals.iter().all(Option::is_none) {
+            vec![]
+        } else {
+            let mut normals = self.normals;
+            for [i0, i1, i2] in &self.indices {
+                let face_normal = (self.positions[*i1] - self.positions[*i0])
+                    .cross(self.positions[*i2] - self.positions[*i0])
+                    .normalize_or_zero();
+                for i in [i0, i1, i2] {
+                    normals[*i].get_or_insert(face_normal);
+                }
+            }
+            normals.into_iter().map(Option::unwrap_or_default).collect()
+        };
+        let uvs = if self.uvs.iter().all(Option::is_none) {
+            vec![]
+        } else {
+            self.uvs.into_iter().map(Option::unwrap_or_default).collect()
+        };
+
+        TriangleMesh::new(self.positions, normals, uvs, self.indices, self.material)
+    }
+}
+
+/// Tokenizer for one line, carrying the location for errors
+struct LineParser<'a> {
+    path: &'a Path,
+    line: usize,
+    rest: &'a str,
+}
+
+impl<'a> LineParser<'a> {
+    fn new(path: &'a Path, line: usize, text: &'a str) -> Self {
+        // everything after a # is a comment
+        let text = text.split('#').next().unwrap_or_default();
+        LineParser {
+            path,
+            line,
+            rest: text.trim(),
+        }
+    }
+
+    fn error(&self, message: &str) -> ObjError {
+        ObjError::Parse {
+            path: self.path.to_path_buf(),
+            line: self.line,
+            message: message.to_string(),
+        }
+    }
+
+    fn next_token(&mut self) -> Option<&'a str> {
+        let trimmed = self.rest.trim_start();
+        if trimmed.is_empty() {
+            return None;
+        }
+        let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        let (token, rest) = trimmed.split_at(end);
+        self.rest = rest;
+        Some(token)
+    }
+
+    /// The remainder of the line, for names that may
+    /// contain spaces
+    fn rest(&self) -> &'a str {
+        self.rest.trim()
+    }
+
+    fn optional_float(&mut self) -> Result<Option<f64>, ObjError> {
+        self.next_token()
+            .map(|token| {
+                token
+                    .parse::<f64>()
+                    .map_err(|_| self.error(&format!("invalid number `{token}`")))
+            })
+            .transpose()
+    }
+
+    fn float(&mut self) -> Result<f64, ObjError> {
+        self.optional_float()?
+            .ok_or_else(|| self.error("expected a number"))
+    }
+
+    fn vec3(&mut self) -> Result<DVec3, ObjError> {
+        Ok(DVec3::new(self.float()?, self.float()?, self.float()?))
+    }
+
+    /// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+    fn face_vertex(
+        &self,
+        token: &str,
+        position_count: usize,
+        uv_count: usize,
+        normal_count: usize,
+    ) -> Result<FaceVertex, ObjError> {
+        let mut parts = token.split('/');
+        let position = parts.next().unwrap_or_default();
+        let uv = parts.next().filter(|part| !part.is_empty());
+        let normal = parts.next().filter(|part| !part.is_empty());
+        if parts.next().is_some() {
+            return Err(self.error(&format!("invalid face vertex `{token}`")));
+        }
+
+        Ok(FaceVertex {
+            position: self.index(position, position_count, "vertex")?,
+            uv: uv
+                .map(|uv| self.index(uv, uv_count, "texture coordinate"))
+                .transpose()?,
+            normal: normal
+                .map(|normal| self.index(normal, normal_count, "normal"))
+                .transpose()?,
+        })
+    }
+
+    /// Turns a 1-based (or negative, counting back from the
+    /// last element) OBJ index into a 0-based one.
+    fn index(&self, token: &str, count: usize, kind: &str) -> Result<usize, ObjError> {
+        let index: i64 = token
+            .parse()
+            .map_err(|_| self.error(&format!("invalid {kind} index `{token}`")))?;
+
+        let resolved = if index > 0 {
+            index - 1
+        } else {
+            count as i64 + index
+        };
+        if index == 0 || resolved < 0 || resolved >= count as i64 {
+            return Err(self.error(&format!(
+                "{kind} index {index} out of range ({count} defined)"
+            )));
+        }
+        Ok(resolved as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `source` to a temporary `.obj` file and loads it
+    fn load_source(name: &str, source: &str) -> Result<Vec<TriangleMesh>, ObjError> {
+        let path = std::env::temp_dir().join(format!("rt-obj-{}-{name}.obj", std::process::id()));
+        fs::write(&path, source).unwrap();
+        let result = load(&path);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    const TRIANGLE: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_vertex() {
+        let meshes = load_source("negative", &format!("v 5 5 5\n{TRIANGLE}f -3 -2 -1\n")).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(
+            meshes[0].positions(),
+            &[DVec3::ZERO, DVec3::X, DVec3::Y]
+        );
+        assert_eq!(meshes[0].indices(), &[[0, 1, 2]]);
+    }
+
+    #[test]
+    fn faces_with_uvs_and_normals() {
+        let source = format!("{TRIANGLE}vt 0 0\nvt 1 0\nvt 0 1\nvn 0 0 1\nf 1/1/1 2/2/1 3/3/1\n");
+        let meshes = load_source("uv-normal", &source).unwrap();
+        assert_eq!(meshes[0].uvs(), &[DVec2::ZERO, DVec2::X, DVec2::Y]);
+        assert_eq!(meshes[0].normals(), &[DVec3::Z; 3]);
+    }
+
+    #[test]
+    fn faces_with_normals_only() {
+        let source = format!("{TRIANGLE}vn 0 0 -1\nf 1//1 2//1 3//1\n");
+        let meshes = load_source("normal", &source).unwrap();
+        assert!(meshes[0].uvs().is_empty());
+        assert_eq!(meshes[0].normals(), &[DVec3::NEG_Z; 3]);
+    }
+
+    #[test]
+    fn polygons_are_fanned_around_the_first_corner() {
+        let meshes = load_source("quad", &format!("{SQUARE}f 1 2 3 4\n")).unwrap();
+        assert_eq!(meshes[0].indices(), &[[0, 1, 2], [0, 2, 3]]);
+
+        let source = format!("{SQUARE}v 0.5 2 0\nf 1 2 3 5 4\n");
+        let meshes = load_source("pentagon", &source).unwrap();
+        assert_eq!(meshes[0].indices(), &[[0, 1, 2], [0, 2, 3], [0, 3, 4]]);
+    }
+
+    #[test]
+    fn bad_index_reports_its_line() {
+        match load_source("bad-index", &format!("{TRIANGLE}\nf 1 2 7\n")) {
+            Err(ObjError::Parse { line, message, .. }) => {
+                assert_eq!(line, 5);
+                assert_eq!(message, "vertex index 7 out of range (3 defined)");
+            }
+            Err(error) => panic!("expected a parse error, got {error}"),
+            Ok(_) => panic!("expected a parse error"),
+        }
+    }
+}
@@ -1,41 +1,231 @@
 use glam::DVec3;
 
-use crate::{hittable::Hittable, material::Scattered};
+use crate::{
+    hittable::{HitRecord, Hittable},
+    material::Scattered,
+    scene::Scene,
+    spectrum::Wavelengths,
+};
 
 pub struct Ray {
     pub origin: DVec3,
     pub direction: DVec3,
+    /// Wavelengths the ray carries in spectral mode, `None`
+    /// when rendering in RGB
+    pub wavelengths: Option<Wavelengths>,
 }
 
 impl Ray {
     pub fn at(&self, t: f64) -> DVec3 {
         self.origin + t * self.direction
     }
-    pub fn color<T>(&self, depth: i32, world: &T) -> DVec3
+    pub fn color<T>(&self, depth: i32, scene: &Scene<T>) -> DVec3
+    where
+        T: Hittable + std::marker::Sync,
+    {
+        self.trace(depth, scene, None)
+    }
+
+    /// `bsdf_pdf` is the density the previous bounce picked this
+    /// ray with, `None` for camera rays and specular bounces.
+    /// Emitters found this way are weighted against the chance
+    /// that light sampling would have found them too.
+    fn trace<T>(
+        &self,
+        depth: i32,
+        scene: &Scene<T>,
+        bsdf_pdf: Option<f64>,
+    ) -> DVec3
     where
         T: Hittable + std::marker::Sync,
     {
         if depth <= 0 {
             return DVec3::new(0., 0., 0.);
         }
+        // light found along this ray, as seen through the
+        // wavelengths it carries
+        let spectral_weight = self
+            .wavelengths
+            .map_or(DVec3::ONE, |wavelengths| wavelengths.weight());
+
         if let Some(rec) =
-            world.hit(&self, (0.001)..f64::INFINITY)
+            scene.world.hit(&self, (0.001)..f64::INFINITY)
         {
+            let mut emitted = rec.material.emitted(&rec);
+            // only an emitter that light sampling could have
+            // reached shares its light with it; glowing media are
+            // never sampled and keep the full weight
+            let sampled_emitter = || {
+                scene.emitters.hit(self, 0.001..rec.t + 1e-6).is_some()
+            };
+            if let Some(bsdf_pdf) = bsdf_pdf {
+                if emitted != DVec3::ZERO && sampled_emitter() {
+                    let light_pdf = scene
+                        .emitters
+                        .pdf_value(self.origin, self.direction);
+                    emitted *= power_heuristic(bsdf_pdf, light_pdf);
+                }
+            }
+
+            let direct = direct_lighting(self, &rec, scene)
+                + sample_emitters(self, &rec, scene)
+                + sample_background(self, &rec, scene);
+            let found = (emitted + direct) * spectral_weight;
             if let Some(Scattered {
                 attenuation,
                 scattered,
+                pdf,
             }) = rec.material.scatter(self, rec.clone())
             {
-                return attenuation
-                    * scattered.color(depth - 1, world);
+                return found
+                    + attenuation
+                        * scattered.trace(depth - 1, scene, pdf);
             }
-            return DVec3::new(0., 0., 0.);
+            return found;
         }
 
-        let unit_direction: DVec3 =
-            self.direction.normalize();
-        let a = 0.5 * (unit_direction.y + 1.0);
-        return (1.0 - a) * DVec3::new(1.0, 1.0, 1.0)
-            + a * DVec3::new(0.5, 0.7, 1.0);
+        let mut background = scene.background.color(self.direction);
+        if let Some(bsdf_pdf) = bsdf_pdf {
+            let light_pdf = scene.background.pdf(self.direction);
+            background *= power_heuristic(bsdf_pdf, light_pdf);
+        }
+        background * spectral_weight
+    }
+}
+
+/// Light reaching the hit point straight from the scene's
+/// lights, with a shadow ray towards each one.
+fn direct_lighting<T>(
+    ray: &Ray,
+    rec: &HitRecord,
+    scene: &Scene<T>,
+) -> DVec3
+where
+    T: Hittable + std::marker::Sync,
+{
+    scene
+        .lights
+        .iter()
+        .map(|light| {
+            let sample = light.sample(rec.point);
+            let response =
+                rec.material.eval(ray, rec, sample.direction);
+            if response == DVec3::ZERO
+                || sample.radiance == DVec3::ZERO
+            {
+                return DVec3::ZERO;
+            }
+
+            let shadow_ray = Ray {
+                origin: rec.point,
+                direction: sample.direction,
+                wavelengths: None,
+            };
+            // solid objects block the light, media dim it
+            let transmittance = scene.world.transmittance(
+                &shadow_ray,
+                0.001..sample.distance - 0.001,
+            );
+            response * sample.radiance * transmittance
+        })
+        .sum()
+}
+
+/// Next event estimation: aims one ray at a random emitter and
+/// weights what it finds with the power heuristic, so emitters
+/// hit by the BSDF-sampled bounce aren't counted twice.
+fn sample_emitters<T>(
+    ray: &Ray,
+    rec: &HitRecord,
+    scene: &Scene<T>,
+) -> DVec3
+where
+    T: Hittable + std::marker::Sync,
+{
+    if scene.emitters.is_empty() {
+        return DVec3::ZERO;
+    }
+
+    let direction = scene.emitters.random(rec.point).normalize();
+    let response = rec.material.eval(ray, rec, direction);
+    if response == DVec3::ZERO {
+        return DVec3::ZERO;
     }
-}
\ No newline at end of file
+    let light_pdf = scene.emitters.pdf_value(rec.point, direction);
+    if light_pdf <= 0. {
+        return DVec3::ZERO;
+    }
+
+    let light_ray = Ray {
+        origin: rec.point,
+        direction,
+        wavelengths: None,
+    };
+    // the emitter the ray was aimed at, dimmed by media and
+    // blocked by surfaces in front of it
+    let Some(light_rec) =
+        scene.emitters.hit(&light_ray, 0.001..f64::INFINITY)
+    else {
+        return DVec3::ZERO;
+    };
+    let transmittance = scene
+        .world
+        .transmittance(&light_ray, 0.001..light_rec.t - 0.001);
+    if transmittance <= 0. {
+        return DVec3::ZERO;
+    }
+
+    let emitted =
+        light_rec.material.emitted(&light_rec) * transmittance;
+    let bsdf_pdf = rec.material.pdf(ray, rec, direction);
+    response * emitted * power_heuristic(light_pdf, bsdf_pdf)
+        / light_pdf
+}
+
+/// Next event estimation for an importance sampled background:
+/// a ray towards a bright part of it that must leave the scene
+/// without hitting anything solid.
+fn sample_background<T>(
+    ray: &Ray,
+    rec: &HitRecord,
+    scene: &Scene<T>,
+) -> DVec3
+where
+    T: Hittable + std::marker::Sync,
+{
+    let Some((direction, light_pdf)) = scene.background.sample()
+    else {
+        return DVec3::ZERO;
+    };
+    let response = rec.material.eval(ray, rec, direction);
+    if response == DVec3::ZERO {
+        return DVec3::ZERO;
+    }
+
+    let shadow_ray = Ray {
+        origin: rec.point,
+        direction,
+        wavelengths: None,
+    };
+    let transmittance =
+        scene.world.transmittance(&shadow_ray, 0.001..f64::INFINITY);
+    if transmittance <= 0. {
+        return DVec3::ZERO;
+    }
+
+    let radiance = scene.background.color(direction) * transmittance;
+    let bsdf_pdf = rec.material.pdf(ray, rec, direction);
+    response * radiance * power_heuristic(light_pdf, bsdf_pdf)
+        / light_pdf
+}
+
+/// MIS weight for a sample drawn with density `pdf` when
+/// `other_pdf` could also have produced it.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let a = pdf * pdf;
+    let b = other_pdf * other_pdf;
+    if a + b == 0. {
+        return 0.;
+    }
+    a / (a + b)
+}
@@ -0,0 +1,45 @@
+use crate::{background::Background, hittable::Hittable, lights::Light};
+
+/// Everything the renderer needs besides the camera: the
+/// geometry and the light sources that aren't geometry.
+pub struct Scene<T: Hittable> {
+    pub world: T,
+    pub lights: Vec<Box<dyn Light>>,
+    /// Emissive objects the renderer aims rays at directly.
+    /// They must also be part of `world` to be seen.
+    pub emitters: Vec<Box<dyn Hittable>>,
+    pub background: Background,
+}
+
+impl<T: Hittable> Scene<T> {
+    pub fn new(world: T) -> Self {
+        Scene {
+            world,
+            lights: vec![],
+            emitters: vec![],
+            background: Background::default(),
+        }
+    }
+
+    pub fn with_light(mut self, light: impl Light + 'static) -> Self {
+        self.lights.push(Box::new(light));
+        self
+    }
+
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Registers an emissive object for light sampling. It has
+    /// to implement `Hittable::pdf_value` and `Hittable::random`:
+    /// spheres, quads, triangles, meshes, cuboids and cylinders
+    /// do, on their own or through `Transformed`, but planes,
+    /// BVHs and media don't, and would darken the MIS-weighted
+    /// light they give off. Share it with the world through an
+    /// `Arc` to avoid building it twice.
+    pub fn with_emitter(mut self, emitter: impl Hittable + 'static) -> Self {
+        self.emitters.push(Box::new(emitter));
+        self
+    }
+}
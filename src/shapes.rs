@@ -1,4 +1,64 @@
 pub mod sphere;
 pub mod cylinder;
+pub mod cube;
+pub mod plane;
+pub mod quad;
+pub mod triangle;
+pub mod mesh;
+
+use glam::DVec3;
+
+use crate::{hittable::Hittable, ray::Ray};
+
+/// Solid angle density of a ray along `direction` that reaches
+/// a flat shape at `t`, when points are sampled uniformly over
+/// its `area`: the area density 1/area scaled by distance² /
+/// |cos| to account for how large the shape looks from there.
+/// `normal` can have any length.
+fn area_pdf_to_solid_angle(
+    t: f64,
+    direction: DVec3,
+    normal: DVec3,
+    area: f64,
+) -> f64 {
+    let distance_squared = t * t * direction.length_squared();
+    let cosine = (direction.dot(normal)
+        / (direction.length() * normal.length()))
+    .abs();
+
+    distance_squared / (cosine * area)
+}
 // are other shapes useful?
 // possible SDF definitions?
+
+/// Solid angle density of aiming at `shape` from `origin` by
+/// picking points uniformly over its whole surface, `area` in
+/// total. A direction can lead to several of those points, like
+/// the near and far side of a closed shape, and each one adds
+/// its share. The cosines come from the surface tangents, which
+/// unlike an interpolated shading normal follow the geometry.
+fn surface_pdf(
+    shape: &impl Hittable,
+    area: f64,
+    origin: DVec3,
+    direction: DVec3,
+) -> f64 {
+    let ray = Ray {
+        origin,
+        direction,
+        wavelengths: None,
+    };
+    let mut pdf = 0.;
+    let mut start = 0.001;
+    while let Some(rec) = shape.hit(&ray, start..f64::INFINITY) {
+        let normal = rec.tangent.cross(rec.bitangent);
+        let normal = if normal.length_squared() > 1e-12 {
+            normal
+        } else {
+            rec.normal
+        };
+        pdf += area_pdf_to_solid_angle(rec.t, direction, normal, area);
+        start = rec.t + 0.0001;
+    }
+    pdf
+}
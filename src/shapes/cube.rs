@@ -0,0 +1,189 @@
+use std::ops::Range;
+
+use glam::{DQuat, DVec3};
+use rand::Rng;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    shapes::surface_pdf,
+};
+
+/// Box with six rectangular faces, optionally rotated
+/// about its center.
+pub struct Cuboid {
+    pub center: DVec3,
+    /// Distance from the center to each face along the
+    /// (unrotated) x, y and z axes
+    pub half_extents: DVec3,
+    pub rotation: DQuat,
+    pub material: Material,
+}
+
+impl Cuboid {
+    /// Axis-aligned box spanning two opposite corners.
+    pub fn new(a: DVec3, b: DVec3, material: Material) -> Self {
+        Cuboid {
+            center: 0.5 * (a + b),
+            half_extents: 0.5 * (a - b).abs(),
+            rotation: DQuat::IDENTITY,
+            material,
+        }
+    }
+
+    pub fn from_center(
+        center: DVec3,
+        half_extents: DVec3,
+        material: Material,
+    ) -> Self {
+        Cuboid {
+            center,
+            half_extents: half_extents.abs(),
+            rotation: DQuat::IDENTITY,
+            material,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: DQuat) -> Self {
+        self.rotation = rotation.normalize();
+        self
+    }
+
+    /// Area of one of the two faces across `axis`
+    fn face_area(&self, axis: usize) -> f64 {
+        4. * self.half_extents[(axis + 1) % 3]
+            * self.half_extents[(axis + 2) % 3]
+    }
+
+    pub fn area(&self) -> f64 {
+        2. * (0..3).map(|axis| self.face_area(axis)).sum::<f64>()
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Range<f64>,
+    ) -> Option<HitRecord> {
+        // Work in box space, where the faces are axis-aligned
+        // slabs around the origin. Rotation keeps t unchanged.
+        let inverse = self.rotation.inverse();
+        let origin = inverse * (ray.origin - self.center);
+        let direction = inverse * ray.direction;
+
+        let mut t_near = f64::NEG_INFINITY;
+        let mut t_far = f64::INFINITY;
+        let mut near_axis = 0;
+        let mut far_axis = 0;
+
+        for axis in 0..3 {
+            let inv_d = direction[axis].recip();
+            let mut t0 =
+                (-self.half_extents[axis] - origin[axis]) * inv_d;
+            let mut t1 =
+                (self.half_extents[axis] - origin[axis]) * inv_d;
+            if inv_d < 0. {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            if t0 > t_near {
+                t_near = t0;
+                near_axis = axis;
+            }
+            if t1 < t_far {
+                t_far = t1;
+                far_axis = axis;
+            }
+            if t_far < t_near {
+                return None;
+            }
+        }
+
+        // Entering through the near face, or leaving through
+        // the far one when the ray starts inside the box
+        let (t, axis, sign) = if interval.contains(&t_near) {
+            (t_near, near_axis, -direction[near_axis].signum())
+        } else if interval.contains(&t_far) {
+            (t_far, far_axis, direction[far_axis].signum())
+        } else {
+            return None;
+        };
+
+        let mut local_normal = DVec3::ZERO;
+        local_normal[axis] = sign;
+        let outward_normal = self.rotation * local_normal;
+
+        // each face spans the unit square along the other
+        // two axes
+        let local_point = origin + t * direction;
+        let face_uv = |other: usize| {
+            0.5 * (local_point[other] / self.half_extents[other] + 1.)
+        };
+        let rec = HitRecord::with_face_normal(
+            self.material.clone(),
+            ray.at(t),
+            outward_normal,
+            t,
+            ray,
+        )
+        .with_uv(face_uv((axis + 1) % 3), face_uv((axis + 2) % 3))
+        .with_tangents(
+            self.rotation * unit_axis((axis + 1) % 3),
+            self.rotation * unit_axis((axis + 2) % 3),
+        );
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        (0..8).fold(Aabb::EMPTY, |acc, i| {
+            let corner = DVec3::new(
+                if i & 1 == 0 { -1. } else { 1. },
+                if i & 2 == 0 { -1. } else { 1. },
+                if i & 4 == 0 { -1. } else { 1. },
+            ) * self.half_extents;
+            let corner = self.rotation * corner + self.center;
+            acc.union(&Aabb::new(corner, corner))
+        })
+    }
+
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let area = self.area();
+        if area <= 0. {
+            return 0.;
+        }
+        surface_pdf(self, area, origin, direction)
+    }
+
+    // pick a face by area, then a point uniformly on it
+    fn random(&self, origin: DVec3) -> DVec3 {
+        let mut rng = rand::thread_rng();
+        let mut target = rng.gen::<f64>() * 0.5 * self.area();
+        let mut axis = 0;
+        while axis < 2 && target >= self.face_area(axis) {
+            target -= self.face_area(axis);
+            axis += 1;
+        }
+
+        let mut local_point = DVec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        ) * self.half_extents;
+        local_point[axis] = if rng.gen() {
+            self.half_extents[axis]
+        } else {
+            -self.half_extents[axis]
+        };
+        self.rotation * local_point + self.center - origin
+    }
+}
+
+fn unit_axis(axis: usize) -> DVec3 {
+    let mut unit = DVec3::ZERO;
+    unit[axis] = 1.;
+    unit
+}
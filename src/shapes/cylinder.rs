@@ -1,6 +1,7 @@
-use std::ops::Range;
-use glam::{ DVec3, Vec3, Mat3 };
-use crate::{ hittable::{ HitRecord, Hittable }, material::Material, ray::Ray };
+use std::{ f64::consts::PI, ops::Range };
+use glam::{ DMat3, DVec3 };
+use rand::Rng;
+use crate::{ aabb::Aabb, hittable::{ HitRecord, Hittable }, material::Material, ray::Ray, shapes::surface_pdf };
 
 pub struct Cylinder {
     pub center: DVec3,
@@ -9,43 +10,72 @@ pub struct Cylinder {
     pub material: Material,
     pub translation: DVec3, // Added translation
     pub rotation: f64, // Added rotation (in radians)
-}
-
-fn to_vec3(v: DVec3) -> Vec3 {
-    Vec3::new(v.x as f32, v.y as f32, v.z as f32)
+    pub inner_radius: f64, // Hollow core for pipes and rings, 0.0 for a solid cylinder
+    pub open_ended: bool, // Skip the top and bottom caps, leaving an open tube
 }
 
 impl Cylinder {
-    fn rotation_matrix_y(angle: f64) -> Mat3 {
-        // Convert the angle to f32
-        Mat3::from_rotation_y(angle as f32)
+    fn rotation_matrix_y(angle: f64) -> DMat3 {
+        DMat3::from_rotation_y(angle)
     }
 
     fn transform_ray(&self, ray: &Ray) -> Ray {
         let rotation_matrix = Self::rotation_matrix_y(-self.rotation);
 
-        // Use the helper function for conversion
-        let origin = to_vec3(ray.origin - self.translation - self.center);
-        let direction = to_vec3(ray.direction);
-
         Ray {
-            origin: DVec3::from(rotation_matrix * origin),
-            direction: DVec3::from(rotation_matrix * direction),
+            origin: rotation_matrix * (ray.origin - self.translation - self.center),
+            direction: rotation_matrix * ray.direction,
+            wavelengths: ray.wavelengths,
         }
     }
-}
 
-impl Hittable for Cylinder {
-    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
-        let transformed_ray = self.transform_ray(ray);
-        let oc = transformed_ray.origin;
+    // Walls wrap u once around the axis and run v up the height;
+    // caps map the disk onto the unit square
+    fn uv(&self, point: DVec3, outward_normal: DVec3) -> (f64, f64) {
+        if outward_normal.y != 0.0 {
+            ((point.x / self.radius + 1.0) / 2.0, (point.z / self.radius + 1.0) / 2.0)
+        } else {
+            let phi = (-point.z).atan2(point.x) + PI;
+            (phi / (2.0 * PI), point.y / self.height)
+        }
+    }
+
+    // Object-space directions in which u and v grow, matching `uv`
+    fn tangents(&self, point: DVec3, outward_normal: DVec3) -> (DVec3, DVec3) {
+        if outward_normal.y != 0.0 {
+            (DVec3::X, DVec3::Z)
+        } else {
+            (DVec3::new(point.z, 0.0, -point.x), DVec3::Y)
+        }
+    }
+
+    // Areas of the outer wall, the inner wall and both caps together
+    fn areas(&self) -> [f64; 3] {
+        let caps = if self.open_ended {
+            0.0
+        } else {
+            2.0 * PI * (self.radius * self.radius - self.inner_radius * self.inner_radius)
+        };
+        [2.0 * PI * self.radius * self.height, 2.0 * PI * self.inner_radius.max(0.0) * self.height, caps]
+    }
+
+    pub fn area(&self) -> f64 {
+        self.areas().iter().sum()
+    }
+
+    // Both roots of the ray against the infinite wall of the given radius
+    fn wall_roots(ray: &Ray, radius: f64) -> Option<[f64; 2]> {
+        let oc = ray.origin;
 
         // These coefficients are part of the quadratic formula solution
-        let a =
-            transformed_ray.direction.x * transformed_ray.direction.x +
-            transformed_ray.direction.z * transformed_ray.direction.z;
-        let b = 2.0 * (oc.x * transformed_ray.direction.x + oc.z * transformed_ray.direction.z);
-        let c = oc.x * oc.x + oc.z * oc.z - self.radius * self.radius;
+        let a = ray.direction.x * ray.direction.x + ray.direction.z * ray.direction.z;
+        let b = 2.0 * (oc.x * ray.direction.x + oc.z * ray.direction.z);
+        let c = oc.x * oc.x + oc.z * oc.z - radius * radius;
+
+        // A ray parallel to the axis never crosses the wall
+        if a < 1e-12 {
+            return None;
+        }
 
         let discriminant = b * b - 4.0 * a * c;
         if discriminant < 0.0 {
@@ -53,39 +83,124 @@ impl Hittable for Cylinder {
         }
 
         let sqrtd = discriminant.sqrt();
+        Some([(-b - sqrtd) / (2.0 * a), (-b + sqrtd) / (2.0 * a)])
+    }
+}
 
-        // Find the nearest root that lies in the acceptable range.
-        let mut root = (-b - sqrtd) / (2.0 * a);
-        if !interval.contains(&root) {
-            root = (-b + sqrtd) / (2.0 * a);
-            if !interval.contains(&root) {
-                return None;
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+        let transformed_ray = self.transform_ray(ray);
+
+        // Nearest (root, object-space outward normal) over all surfaces
+        let mut closest: Option<(f64, DVec3)> = None;
+        let mut consider = |root: f64, normal: DVec3| {
+            if interval.contains(&root) && closest.is_none_or(|(t, _)| root < t) {
+                closest = Some((root, normal));
+            }
+        };
+
+        // Check if the intersection point is within the height of the cylinder
+        let within_height = |point: DVec3| point.y >= 0.0 && point.y <= self.height;
+
+        for root in Self::wall_roots(&transformed_ray, self.radius).into_iter().flatten() {
+            let point = transformed_ray.at(root);
+            if within_height(point) {
+                consider(root, DVec3::new(point.x, 0.0, point.z) / self.radius);
             }
         }
 
-        let point = transformed_ray.at(root);
+        // The inner wall faces the axis
+        if self.inner_radius > 0.0 {
+            for root in Self::wall_roots(&transformed_ray, self.inner_radius).into_iter().flatten() {
+                let point = transformed_ray.at(root);
+                if within_height(point) {
+                    consider(root, -DVec3::new(point.x, 0.0, point.z) / self.inner_radius);
+                }
+            }
+        }
 
-        // Check if the intersection point is within the height of the cylinder
-        if point.y < 0.0 || point.y > self.height {
-            return None;
+        // Caps are rings between the inner and outer radius
+        if !self.open_ended && transformed_ray.direction.y.abs() > 1e-12 {
+            for (cap_y, normal) in [(0.0, DVec3::NEG_Y), (self.height, DVec3::Y)] {
+                let root = (cap_y - transformed_ray.origin.y) / transformed_ray.direction.y;
+                let point = transformed_ray.at(root);
+                let distance_squared = point.x * point.x + point.z * point.z;
+                if distance_squared <= self.radius * self.radius
+                    && distance_squared >= self.inner_radius * self.inner_radius
+                {
+                    consider(root, normal);
+                }
+            }
         }
 
-        let outward_normal = DVec3::new(point.x, 0.0, point.z).normalize();
+        let (root, outward_normal) = closest?;
+        let point = transformed_ray.at(root);
 
         // Reverse the transformations for the normal and the hit point
         let rotation_matrix = Self::rotation_matrix_y(self.rotation);
-        let transformed_normal = DVec3::from(rotation_matrix * to_vec3(outward_normal));
-        let transformed_point =
-            DVec3::from(rotation_matrix * to_vec3(point)) + self.translation + self.center;
+        let transformed_normal = rotation_matrix * outward_normal;
+        let transformed_point = rotation_matrix * point + self.translation + self.center;
 
+        let (u, v) = self.uv(point, outward_normal);
+        let (tangent, bitangent) = self.tangents(point, outward_normal);
         let rec = HitRecord::with_face_normal(
             self.material.clone(),
             transformed_point,
             transformed_normal,
             root,
             ray
-        );
+        )
+            .with_uv(u, v)
+            .with_tangents(rotation_matrix * tangent, rotation_matrix * bitangent);
 
         Some(rec)
     }
+
+    fn bounding_box(&self) -> Aabb {
+        // Rotate the corners of the object-space box and take their extent
+        let rotation_matrix = Self::rotation_matrix_y(self.rotation);
+        let local = Aabb::new(
+            DVec3::new(-self.radius, 0.0, -self.radius),
+            DVec3::new(self.radius, self.height, self.radius)
+        );
+        let offset = self.translation + self.center;
+
+        (0..8).fold(Aabb::EMPTY, |acc, i| {
+            let corner = DVec3::new(
+                if i & 1 == 0 { local.min.x } else { local.max.x },
+                if i & 2 == 0 { local.min.y } else { local.max.y },
+                if i & 4 == 0 { local.min.z } else { local.max.z }
+            );
+            let corner = rotation_matrix * corner + offset;
+            acc.union(&Aabb::new(corner, corner))
+        })
+    }
+
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let area = self.area();
+        if area <= 0.0 {
+            return 0.0;
+        }
+        surface_pdf(self, area, origin, direction)
+    }
+
+    fn random(&self, origin: DVec3) -> DVec3 {
+        let mut rng = rand::thread_rng();
+        let [outer, inner, _caps] = self.areas();
+        let target = rng.gen::<f64>() * self.area();
+        let phi = rng.gen_range(0.0..2.0 * PI);
+
+        // Pick a surface by area, then a point uniformly on it
+        let point = if target < outer + inner {
+            let radius = if target < outer { self.radius } else { self.inner_radius };
+            DVec3::new(radius * phi.cos(), rng.gen_range(0.0..self.height), radius * phi.sin())
+        } else {
+            let inner_squared = self.inner_radius * self.inner_radius;
+            let radius = (inner_squared + rng.gen::<f64>() * (self.radius * self.radius - inner_squared)).sqrt();
+            let y = if rng.gen() { 0.0 } else { self.height };
+            DVec3::new(radius * phi.cos(), y, radius * phi.sin())
+        };
+
+        Self::rotation_matrix_y(self.rotation) * point + self.translation + self.center - origin
+    }
 }
@@ -0,0 +1,233 @@
+use std::{ops::Range, sync::Arc};
+
+use glam::{DVec2, DVec3};
+use rand::Rng;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{bvh::BvhNode, HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+    shapes::{surface_pdf, triangle},
+};
+
+/// Indexed triangle mesh.
+///
+/// Vertex attributes live in shared buffers and every face is a
+/// triple of indices into them, so a vertex used by several
+/// faces is stored once. The faces are kept in their own BVH,
+/// which makes a mesh a single object in the world.
+pub struct TriangleMesh {
+    buffers: Arc<MeshBuffers>,
+    bvh: BvhNode,
+    /// Running total of the face areas, for picking a face in
+    /// proportion to its size when sampling the mesh as a light
+    area_cdf: Vec<f64>,
+}
+
+struct MeshBuffers {
+    positions: Vec<DVec3>,
+    /// Per-vertex normals for smooth shading, or empty for
+    /// flat-shaded faces
+    normals: Vec<DVec3>,
+    /// Per-vertex texture coordinates, or empty
+    uvs: Vec<DVec2>,
+    indices: Vec<[usize; 3]>,
+    material: Material,
+}
+
+/// One face of a mesh, looked up through the shared buffers
+struct MeshTriangle {
+    buffers: Arc<MeshBuffers>,
+    face: usize,
+}
+
+impl TriangleMesh {
+    /// `normals` and `uvs` are either empty or hold one entry
+    /// per position. Faces are counter-clockwise seen from the
+    /// front.
+    ///
+    /// Panics if the buffers don't line up, an index is out of
+    /// range or there are no faces.
+    pub fn new(
+        positions: Vec<DVec3>,
+        normals: Vec<DVec3>,
+        uvs: Vec<DVec2>,
+        indices: Vec<[usize; 3]>,
+        material: Material,
+    ) -> Self {
+        assert!(
+            normals.is_empty() || normals.len() == positions.len(),
+            "expected one normal per vertex"
+        );
+        assert!(
+            uvs.is_empty() || uvs.len() == positions.len(),
+            "expected one uv per vertex"
+        );
+        assert!(
+            indices.iter().flatten().all(|&i| i < positions.len()),
+            "face index out of range"
+        );
+
+        let face_count = indices.len();
+        let area_cdf = indices
+            .iter()
+            .scan(0., |total, &[i0, i1, i2]| {
+                let (v0, v1, v2) = (positions[i0], positions[i1], positions[i2]);
+                *total += 0.5 * (v1 - v0).cross(v2 - v0).length();
+                Some(*total)
+            })
+            .collect();
+        let buffers = Arc::new(MeshBuffers {
+            positions,
+            normals,
+            uvs,
+            indices,
+            material,
+        });
+        let triangles = (0..face_count)
+            .map(|face| {
+                Box::new(MeshTriangle {
+                    buffers: buffers.clone(),
+                    face,
+                }) as Box<dyn Hittable>
+            })
+            .collect();
+
+        TriangleMesh {
+            bvh: BvhNode::new(triangles),
+            buffers,
+            area_cdf,
+        }
+    }
+
+    pub fn positions(&self) -> &[DVec3] {
+        &self.buffers.positions
+    }
+    pub fn normals(&self) -> &[DVec3] {
+        &self.buffers.normals
+    }
+    pub fn uvs(&self) -> &[DVec2] {
+        &self.buffers.uvs
+    }
+    pub fn indices(&self) -> &[[usize; 3]] {
+        &self.buffers.indices
+    }
+    pub fn material(&self) -> &Material {
+        &self.buffers.material
+    }
+
+    /// Total surface area of the faces
+    pub fn area(&self) -> f64 {
+        self.area_cdf.last().copied().unwrap_or(0.)
+    }
+}
+
+impl Hittable for TriangleMesh {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+        self.bvh.hit(ray, interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+
+    fn pdf_value(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let area = self.area();
+        if area <= 0. {
+            return 0.;
+        }
+        surface_pdf(self, area, origin, direction)
+    }
+
+    // pick a face by area, then a point uniformly within it
+    fn random(&self, origin: DVec3) -> DVec3 {
+        let target = rand::thread_rng().gen::<f64>() * self.area();
+        let face = self
+            .area_cdf
+            .partition_point(|&total| total <= target)
+            .min(self.area_cdf.len() - 1);
+        let [i0, i1, i2] = self.buffers.indices[face];
+        let positions = &self.buffers.positions;
+        triangle::random_point(positions[i0], positions[i1], positions[i2]) - origin
+    }
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> (DVec3, DVec3, DVec3) {
+        let [i0, i1, i2] = self.buffers.indices[self.face];
+        let positions = &self.buffers.positions;
+        (positions[i0], positions[i1], positions[i2])
+    }
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(&self, ray: &Ray, interval: Range<f64>) -> Option<HitRecord> {
+        let (v0, v1, v2) = self.vertices();
+        let hit = triangle::intersect(ray, &interval, v0, v1, v2)?;
+        let geometric_normal = (v1 - v0).cross(v2 - v0).normalize();
+
+        let outward_normal = if self.buffers.normals.is_empty() {
+            geometric_normal
+        } else {
+            let [i0, i1, i2] = self.buffers.indices[self.face];
+            let normals = &self.buffers.normals;
+            let b0 = 1. - hit.b1 - hit.b2;
+            let shading_normal =
+                (b0 * normals[i0] + hit.b1 * normals[i1] + hit.b2 * normals[i2])
+                    .normalize_or_zero();
+
+            // keep the interpolated normal on the same side as the
+            // face so front_face agrees with the winding order
+            if shading_normal == DVec3::ZERO {
+                geometric_normal
+            } else if shading_normal.dot(geometric_normal) < 0. {
+                -shading_normal
+            } else {
+                shading_normal
+            }
+        };
+
+        // without vertex uvs, fall back to the barycentric
+        // coordinates like a lone `Triangle`
+        let (edge1, edge2) = (v1 - v0, v2 - v0);
+        let (uv, tangent, bitangent) = if self.buffers.uvs.is_empty() {
+            (DVec2::new(hit.b1, hit.b2), edge1, edge2)
+        } else {
+            let [i0, i1, i2] = self.buffers.indices[self.face];
+            let uvs = &self.buffers.uvs;
+            let uv =
+                (1. - hit.b1 - hit.b2) * uvs[i0] + hit.b1 * uvs[i1] + hit.b2 * uvs[i2];
+
+            // solve edge = du * tangent + dv * bitangent for
+            // both edges
+            let delta1 = uvs[i1] - uvs[i0];
+            let delta2 = uvs[i2] - uvs[i0];
+            let det = delta1.perp_dot(delta2);
+            if det.abs() < 1e-12 {
+                (uv, edge1, edge2)
+            } else {
+                let tangent = (edge1 * delta2.y - edge2 * delta1.y) / det;
+                let bitangent = (edge2 * delta1.x - edge1 * delta2.x) / det;
+                (uv, tangent, bitangent)
+            }
+        };
+
+        let rec = HitRecord::with_face_normal(
+            self.buffers.material.clone(),
+            ray.at(hit.t),
+            outward_normal,
+            hit.t,
+            ray,
+        )
+        .with_uv(uv.x, uv.y)
+        .with_tangents(tangent, bitangent);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let (v0, v1, v2) = self.vertices();
+        triangle::bounding_box(v0, v1, v2)
+    }
+}
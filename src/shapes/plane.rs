@@ -0,0 +1,64 @@
+use std::ops::Range;
+
+use glam::DVec3;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+};
+
+/// Infinite plane through `point`. The side `normal` points
+/// to counts as the front face.
+pub struct Plane {
+    pub point: DVec3,
+    pub normal: DVec3,
+    pub material: Material,
+}
+
+impl Hittable for Plane {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Range<f64>,
+    ) -> Option<HitRecord> {
+        let normal = self.normal.normalize();
+        let denom = normal.dot(ray.direction);
+
+        // ray runs parallel to the plane
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+
+        let t = (self.point - ray.origin).dot(normal) / denom;
+        if !interval.contains(&t) {
+            return None;
+        }
+
+        // texture coordinates in world units along two
+        // directions in the plane, so image textures tile
+        let point = ray.at(t);
+        let (tangent, bitangent) = normal.any_orthonormal_pair();
+        let offset = point - self.point;
+        let rec = HitRecord::with_face_normal(
+            self.material.clone(),
+            point,
+            normal,
+            t,
+            ray,
+        )
+        .with_uv(offset.dot(tangent), offset.dot(bitangent))
+        .with_tangents(tangent, bitangent);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // unbounded, the BVH keeps planes out of the tree
+        Aabb::new(
+            DVec3::splat(f64::NEG_INFINITY),
+            DVec3::splat(f64::INFINITY),
+        )
+    }
+}
@@ -0,0 +1,122 @@
+use std::ops::Range;
+
+use glam::DVec3;
+use rand::Rng;
+
+use super::area_pdf_to_solid_angle;
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+};
+
+/// Parallelogram spanned by the edges `u` and `v` from
+/// `corner`. The front face is on the side of `u × v`, which
+/// makes it a good fit for light panels.
+pub struct Quad {
+    pub corner: DVec3,
+    pub u: DVec3,
+    pub v: DVec3,
+    pub material: Material,
+}
+
+impl Quad {
+    fn area(&self) -> f64 {
+        self.u.cross(self.v).length()
+    }
+
+    /// Ray parameter and (alpha, beta) coordinates along `u`
+    /// and `v` where the ray crosses the quad
+    fn intersect(
+        &self,
+        ray: &Ray,
+        interval: &Range<f64>,
+    ) -> Option<(f64, f64, f64)> {
+        let n = self.u.cross(self.v);
+        let denom = n.dot(ray.direction);
+
+        // ray runs parallel to the quad
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+
+        let t = (self.corner - ray.origin).dot(n) / denom;
+        if !interval.contains(&t) {
+            return None;
+        }
+
+        // express the hit point in the (u, v) frame
+        let w = n / n.length_squared();
+        let planar = ray.at(t) - self.corner;
+        let alpha = w.dot(planar.cross(self.v));
+        let beta = w.dot(self.u.cross(planar));
+        if !(0. ..=1.).contains(&alpha) || !(0. ..=1.).contains(&beta) {
+            return None;
+        }
+
+        Some((t, alpha, beta))
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Range<f64>,
+    ) -> Option<HitRecord> {
+        let (t, alpha, beta) = self.intersect(ray, &interval)?;
+        let outward_normal = self.u.cross(self.v).normalize();
+
+        let rec = HitRecord::with_face_normal(
+            self.material.clone(),
+            ray.at(t),
+            outward_normal,
+            t,
+            ray,
+        )
+        .with_uv(alpha, beta)
+        .with_tangents(self.u, self.v);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let opposite = self.corner + self.u + self.v;
+        Aabb::new(self.corner, opposite)
+            .union(&Aabb::new(self.corner + self.u, self.corner + self.v))
+            .pad(1e-8)
+    }
+
+    fn pdf_value(
+        &self,
+        origin: DVec3,
+        direction: DVec3,
+    ) -> f64 {
+        let ray = Ray {
+            origin,
+            direction,
+            wavelengths: None,
+        };
+        let Some((t, _, _)) =
+            self.intersect(&ray, &(0.001..f64::INFINITY))
+        else {
+            return 0.;
+        };
+
+        area_pdf_to_solid_angle(
+            t,
+            direction,
+            self.u.cross(self.v),
+            self.area(),
+        )
+    }
+
+    fn random(&self, origin: DVec3) -> DVec3 {
+        let mut rng = rand::thread_rng();
+        let point = self.corner
+            + rng.gen::<f64>() * self.u
+            + rng.gen::<f64>() * self.v;
+        point - origin
+    }
+}
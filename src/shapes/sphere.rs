@@ -1,8 +1,10 @@
 use std::ops::Range;
 
 use glam::DVec3;
+use rand::Rng;
 
 use crate::{
+    aabb::Aabb,
     hittable::{HitRecord, Hittable},
     material::Material,
     ray::Ray,
@@ -46,14 +48,98 @@ impl Hittable for Sphere {
         let outward_normal =
             (point - self.center) / self.radius;
 
+        let (u, v) = Self::uv(outward_normal);
+        // u runs around the y axis, v from pole to pole
+        let tangent =
+            DVec3::new(outward_normal.z, 0., -outward_normal.x);
         let rec = HitRecord::with_face_normal(
             self.material.clone(),
             point,
             outward_normal,
             t,
             ray,
-        );
+        )
+        .with_uv(u, v)
+        .with_tangents(tangent, outward_normal.cross(tangent));
 
         Some(rec)
     }
+
+    fn bounding_box(&self) -> Aabb {
+        let radius = DVec3::splat(self.radius.abs());
+        Aabb::new(self.center - radius, self.center + radius)
+    }
+
+    // Directions are sampled uniformly inside the cone
+    // the sphere subtends as seen from `origin`
+    fn pdf_value(
+        &self,
+        origin: DVec3,
+        direction: DVec3,
+    ) -> f64 {
+        let ray = Ray {
+            origin,
+            direction,
+            wavelengths: None,
+        };
+        if self.hit(&ray, 0.001..f64::INFINITY).is_none() {
+            return 0.;
+        }
+
+        match self.cos_theta_max(origin) {
+            Some(cos_theta_max) => {
+                let solid_angle = 2.
+                    * std::f64::consts::PI
+                    * (1. - cos_theta_max);
+                solid_angle.recip()
+            }
+            None => 0.,
+        }
+    }
+
+    fn random(&self, origin: DVec3) -> DVec3 {
+        let Some(cos_theta_max) = self.cos_theta_max(origin)
+        else {
+            return DVec3::X;
+        };
+
+        let mut rng = rand::thread_rng();
+        let r1 = rng.gen::<f64>();
+        let r2 = rng.gen::<f64>();
+        let z = 1. + r2 * (cos_theta_max - 1.);
+        let phi = 2. * std::f64::consts::PI * r1;
+        let sin_theta = (1. - z * z).sqrt();
+
+        let w = (self.center - origin).normalize();
+        let (u, v) = w.any_orthonormal_pair();
+        sin_theta * phi.cos() * u
+            + sin_theta * phi.sin() * v
+            + z * w
+    }
+}
+
+impl Sphere {
+    /// Texture coordinates of a point on the unit sphere: `u`
+    /// goes around the y axis starting at -x, `v` from the
+    /// bottom pole (0) to the top (1).
+    fn uv(point: DVec3) -> (f64, f64) {
+        let theta = (-point.y).clamp(-1., 1.).acos();
+        let phi = (-point.z).atan2(point.x) + std::f64::consts::PI;
+        (
+            phi / (2. * std::f64::consts::PI),
+            theta / std::f64::consts::PI,
+        )
+    }
+
+    /// Cosine of the half-angle of the cone the sphere fills
+    /// as seen from `origin`, or `None` from inside it.
+    fn cos_theta_max(&self, origin: DVec3) -> Option<f64> {
+        let distance_squared =
+            (self.center - origin).length_squared();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            return None;
+        }
+        Some((1. - radius_squared / distance_squared).sqrt())
+    }
 }
\ No newline at end of file
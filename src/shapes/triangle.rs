@@ -0,0 +1,141 @@
+use std::ops::Range;
+
+use glam::DVec3;
+use rand::Rng;
+
+use super::area_pdf_to_solid_angle;
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    material::Material,
+    ray::Ray,
+};
+
+/// Single flat triangle. Vertices in counter-clockwise order
+/// (seen from the front) give the outward normal.
+pub struct Triangle {
+    pub v0: DVec3,
+    pub v1: DVec3,
+    pub v2: DVec3,
+    pub material: Material,
+}
+
+/// Where a ray crosses a triangle: the ray parameter and the
+/// barycentric weights of `v1` and `v2`
+pub(crate) struct TriangleHit {
+    pub t: f64,
+    pub b1: f64,
+    pub b2: f64,
+}
+
+/// Möller–Trumbore ray/triangle intersection.
+pub(crate) fn intersect(
+    ray: &Ray,
+    interval: &Range<f64>,
+    v0: DVec3,
+    v1: DVec3,
+    v2: DVec3,
+) -> Option<TriangleHit> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = ray.direction.cross(edge2);
+    let det = edge1.dot(p);
+
+    // ray is parallel to the triangle's plane
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = det.recip();
+
+    let s = ray.origin - v0;
+    let b1 = s.dot(p) * inv_det;
+    if !(0. ..=1.).contains(&b1) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let b2 = ray.direction.dot(q) * inv_det;
+    if b2 < 0. || b1 + b2 > 1. {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inv_det;
+    if !interval.contains(&t) {
+        return None;
+    }
+
+    Some(TriangleHit { t, b1, b2 })
+}
+
+/// Point picked uniformly over the area of a triangle.
+pub(crate) fn random_point(v0: DVec3, v1: DVec3, v2: DVec3) -> DVec3 {
+    let mut rng = rand::thread_rng();
+    let r1 = rng.gen::<f64>().sqrt();
+    let r2 = rng.gen::<f64>();
+    (1. - r1) * v0 + r1 * (1. - r2) * v1 + r1 * r2 * v2
+}
+
+pub(crate) fn bounding_box(v0: DVec3, v1: DVec3, v2: DVec3) -> Aabb {
+    Aabb::new(v0.min(v1).min(v2), v0.max(v1).max(v2)).pad(1e-8)
+}
+
+impl Hittable for Triangle {
+    fn hit(
+        &self,
+        ray: &Ray,
+        interval: Range<f64>,
+    ) -> Option<HitRecord> {
+        let hit = intersect(ray, &interval, self.v0, self.v1, self.v2)?;
+        let outward_normal =
+            (self.v1 - self.v0).cross(self.v2 - self.v0).normalize();
+
+        let rec = HitRecord::with_face_normal(
+            self.material.clone(),
+            ray.at(hit.t),
+            outward_normal,
+            hit.t,
+            ray,
+        )
+        .with_uv(hit.b1, hit.b2)
+        .with_tangents(self.v1 - self.v0, self.v2 - self.v0);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        bounding_box(self.v0, self.v1, self.v2)
+    }
+
+    fn pdf_value(
+        &self,
+        origin: DVec3,
+        direction: DVec3,
+    ) -> f64 {
+        let ray = Ray {
+            origin,
+            direction,
+            wavelengths: None,
+        };
+        let Some(hit) = intersect(
+            &ray,
+            &(0.001..f64::INFINITY),
+            self.v0,
+            self.v1,
+            self.v2,
+        ) else {
+            return 0.;
+        };
+
+        let cross = (self.v1 - self.v0).cross(self.v2 - self.v0);
+        area_pdf_to_solid_angle(
+            hit.t,
+            direction,
+            cross,
+            0.5 * cross.length(),
+        )
+    }
+
+    fn random(&self, origin: DVec3) -> DVec3 {
+        random_point(self.v0, self.v1, self.v2) - origin
+    }
+}
@@ -0,0 +1,143 @@
+//! Spectral rendering: the wavelengths a camera sample traces
+//! and how light at those wavelengths turns back into RGB.
+//!
+//! Materials and lights stay RGB. A path carries a handful of
+//! wavelengths, and whatever light reaches it is filtered by
+//! the color those wavelengths stand for. Averaged over many
+//! samples the filters add up to white, so the result only
+//! differs from RGB mode where wavelength changes a path, as in
+//! dispersive glass.
+use std::sync::OnceLock;
+
+use glam::{DMat3, DVec3};
+use rand::Rng;
+
+/// Shortest wavelength traced, in nanometers
+pub const WAVELENGTH_MIN: f64 = 360.;
+/// Longest wavelength traced, in nanometers
+pub const WAVELENGTH_MAX: f64 = 830.;
+/// Wavelengths traced together along one path
+const HERO_COUNT: usize = 4;
+
+/// Hero wavelength sampling, after Wilkie et al., "Hero
+/// Wavelength Spectral Sampling" (2014): one wavelength picked
+/// at random, with the others spread evenly over the range
+/// from there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wavelengths {
+    lambdas: [f64; HERO_COUNT],
+    /// How many of `lambdas` the path still carries
+    count: usize,
+}
+
+impl Wavelengths {
+    pub fn sample() -> Self {
+        let range = WAVELENGTH_MAX - WAVELENGTH_MIN;
+        let hero = rand::thread_rng().gen::<f64>() * range;
+        let lambdas = std::array::from_fn(|i| {
+            let offset = i as f64 * range / HERO_COUNT as f64;
+            WAVELENGTH_MIN + (hero + offset).rem_euclid(range)
+        });
+        Wavelengths {
+            lambdas,
+            count: HERO_COUNT,
+        }
+    }
+
+    /// The wavelength that decides where the path goes, in
+    /// nanometers.
+    pub fn hero(&self) -> f64 {
+        self.lambdas[0]
+    }
+
+    /// Drops all but the hero wavelength, for when light of
+    /// different wavelengths would go different ways.
+    pub fn hero_only(self) -> Self {
+        Wavelengths { count: 1, ..self }
+    }
+
+    /// Color filter for light carried at these wavelengths.
+    /// It averages to white over all samples, but single
+    /// channels can be negative where the wavelengths lie
+    /// outside the sRGB gamut.
+    pub fn weight(&self) -> DVec3 {
+        let lambdas = &self.lambdas[..self.count];
+        lambdas
+            .iter()
+            .map(|&lambda| rgb_response(lambda))
+            .sum::<DVec3>()
+            / lambdas.len() as f64
+    }
+}
+
+/// CIE 1931 2° color matching functions at `lambda`
+/// nanometers, from the multi-lobe fit of Wyman, Sloan and
+/// Shirley, "Simple Analytic Approximations to the CIE XYZ
+/// Color Matching Functions" (2013).
+pub fn cie_xyz(lambda: f64) -> DVec3 {
+    // Gaussian with a different width on either side
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let width = if lambda < mean { below } else { above };
+        let t = (lambda - mean) / width;
+        (-0.5 * t * t).exp()
+    };
+    DVec3::new(
+        1.056 * lobe(599.8, 37.9, 31.0)
+            + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5)
+            + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0)
+            + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
+
+/// CIE XYZ to linear sRGB, for a D65 white point.
+pub fn xyz_to_rgb(xyz: DVec3) -> DVec3 {
+    DMat3::from_cols(
+        DVec3::new(3.2406, -0.9689, 0.0557),
+        DVec3::new(-1.5372, 1.8758, -0.2040),
+        DVec3::new(-0.4986, 0.0415, 1.0570),
+    ) * xyz
+}
+
+/// Relative luminance of a linear sRGB color, the Y of CIE XYZ.
+pub fn luminance(color: DVec3) -> f64 {
+    color.dot(DVec3::new(0.2126, 0.7152, 0.0722))
+}
+
+/// Color of a surface that reflects `reflectance(λ)` of the
+/// light at each wavelength, under light that is white in RGB
+/// mode. Reflectances that also depend on the RGB channel are
+/// weighted channel by channel.
+pub fn reflectance_to_rgb(reflectance: impl Fn(f64) -> DVec3) -> DVec3 {
+    let steps = 40;
+    let step = (WAVELENGTH_MAX - WAVELENGTH_MIN) / steps as f64;
+    (0..steps)
+        .map(|i| {
+            let lambda = WAVELENGTH_MIN + (i as f64 + 0.5) * step;
+            reflectance(lambda) * rgb_response(lambda)
+        })
+        .sum::<DVec3>()
+        / steps as f64
+}
+
+/// Linear sRGB response to light at `lambda` nanometers,
+/// scaled so each channel averages to one over the traced
+/// range
+fn rgb_response(lambda: f64) -> DVec3 {
+    static NORMALIZATION: OnceLock<DVec3> = OnceLock::new();
+    let normalization = NORMALIZATION.get_or_init(|| {
+        let steps = 1000;
+        let step = (WAVELENGTH_MAX - WAVELENGTH_MIN) / steps as f64;
+        let mean = (0..steps)
+            .map(|i| {
+                let lambda = WAVELENGTH_MIN + (i as f64 + 0.5) * step;
+                xyz_to_rgb(cie_xyz(lambda))
+            })
+            .sum::<DVec3>()
+            / steps as f64;
+        mean.recip()
+    });
+    xyz_to_rgb(cie_xyz(lambda)) * *normalization
+}
@@ -0,0 +1,164 @@
+use std::{io, path::Path, sync::Arc};
+
+use glam::DVec3;
+
+use crate::image::Image;
+
+pub mod perlin;
+
+use perlin::Perlin;
+
+/// Color that varies over a surface, looked up by the texture
+/// coordinates and position of a hit.
+#[derive(Debug, Clone)]
+pub enum Texture {
+    Solid(DVec3),
+    /// Alternates between `even` and `odd` in cubes of side
+    /// `scale`, so it doesn't need texture coordinates
+    Checker {
+        scale: f64,
+        even: Arc<Texture>,
+        odd: Arc<Texture>,
+    },
+    /// Picture wrapped over the surface's (u, v) square,
+    /// repeating outside of it
+    Image(Arc<Image>),
+    /// Veined stone: sine bands along z, bent by turbulence
+    Marble {
+        noise: Arc<Perlin>,
+        /// Frequency of the pattern, in bands per unit
+        scale: f64,
+        light: DVec3,
+        dark: DVec3,
+    },
+    /// Growth rings around the y axis, wobbled by noise
+    Wood {
+        noise: Arc<Perlin>,
+        /// Frequency of the pattern, in rings per unit
+        scale: f64,
+        light: DVec3,
+        dark: DVec3,
+    },
+    /// `cloud` where fractal noise is high, `sky` in between
+    Clouds {
+        noise: Arc<Perlin>,
+        /// Frequency of the pattern, in clouds per unit
+        scale: f64,
+        sky: DVec3,
+        cloud: DVec3,
+    },
+}
+
+impl From<DVec3> for Texture {
+    fn from(color: DVec3) -> Self {
+        Texture::Solid(color)
+    }
+}
+
+impl Texture {
+    pub fn checker(
+        scale: f64,
+        even: impl Into<Texture>,
+        odd: impl Into<Texture>,
+    ) -> Self {
+        Texture::Checker {
+            scale,
+            even: Arc::new(even.into()),
+            odd: Arc::new(odd.into()),
+        }
+    }
+
+    /// White marble with gray veins.
+    pub fn marble(scale: f64) -> Self {
+        Texture::Marble {
+            noise: Arc::new(Perlin::default()),
+            scale,
+            light: DVec3::new(0.9, 0.9, 0.88),
+            dark: DVec3::new(0.25, 0.25, 0.28),
+        }
+    }
+
+    /// Pale wood with darker rings.
+    pub fn wood(scale: f64) -> Self {
+        Texture::Wood {
+            noise: Arc::new(Perlin::default()),
+            scale,
+            light: DVec3::new(0.75, 0.55, 0.33),
+            dark: DVec3::new(0.4, 0.24, 0.12),
+        }
+    }
+
+    /// White clouds on a blue sky.
+    pub fn clouds(scale: f64) -> Self {
+        Texture::Clouds {
+            noise: Arc::new(Perlin::default()),
+            scale,
+            sky: DVec3::new(0.3, 0.5, 0.85),
+            cloud: DVec3::ONE,
+        }
+    }
+
+    /// Loads an image texture, see `Image::load`.
+    pub fn load_image(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Texture::Image(Arc::new(Image::load(path)?)))
+    }
+
+    /// Color at texture coordinates `u`, `v` and position
+    /// `point`.
+    pub fn value(&self, u: f64, v: f64, point: DVec3) -> DVec3 {
+        match self {
+            Texture::Solid(color) => *color,
+            Texture::Checker { scale, even, odd } => {
+                let cell = (point / *scale).floor();
+                let parity = (cell.x + cell.y + cell.z) as i64;
+                if parity.rem_euclid(2) == 0 {
+                    even.value(u, v, point)
+                } else {
+                    odd.value(u, v, point)
+                }
+            }
+            Texture::Image(image) => {
+                image.sample(u.rem_euclid(1.), v.rem_euclid(1.))
+            }
+            Texture::Marble {
+                noise,
+                scale,
+                light,
+                dark,
+            } => {
+                let p = *scale * point;
+                let phase = p.z + 10. * noise.turbulence(p, 7);
+                dark.lerp(*light, 0.5 * (1. + phase.sin()))
+            }
+            Texture::Wood {
+                noise,
+                scale,
+                light,
+                dark,
+            } => {
+                let p = *scale * point;
+                let radius = (p.x * p.x + p.z * p.z).sqrt();
+                // position within the ring, dark towards
+                // its outer edge
+                let ring =
+                    (radius + 0.5 * noise.noise(2. * p)).rem_euclid(1.);
+                light.lerp(*dark, ring.powi(3))
+            }
+            Texture::Clouds {
+                noise,
+                scale,
+                sky,
+                cloud,
+            } => {
+                let density = 0.5 + noise.fbm(*scale * point, 6);
+                sky.lerp(*cloud, smoothstep(0.45, 0.75, density))
+            }
+        }
+    }
+}
+
+/// Hermite step from 0 at `edge0` to 1 at `edge1`
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
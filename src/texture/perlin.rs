@@ -0,0 +1,122 @@
+use std::fmt;
+
+use glam::DVec3;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise after Ken Perlin: smooth pseudo-random values
+/// within `[-1, 1]` (mostly within ±0.7) that vary on the
+/// scale of one unit.
+pub struct Perlin {
+    gradients: Vec<DVec3>,
+    permutations: [Vec<usize>; 3],
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+impl Perlin {
+    /// The same seed gives the same noise, so renders are
+    /// repeatable.
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                DVec3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .normalize_or_zero()
+            })
+            .collect();
+        let mut permutation = || {
+            let mut indices: Vec<usize> = (0..POINT_COUNT).collect();
+            indices.shuffle(&mut rng);
+            indices
+        };
+        let permutations =
+            [permutation(), permutation(), permutation()];
+
+        Perlin {
+            gradients,
+            permutations,
+        }
+    }
+
+    /// Noise value at `point`.
+    pub fn noise(&self, point: DVec3) -> f64 {
+        let cell = point.floor();
+        let offset = point - cell;
+        // Hermite smoothing hides the lattice
+        let weight = offset * offset * (3. - 2. * offset);
+
+        let mut sum = 0.;
+        for corner in 0..8 {
+            let corner = DVec3::new(
+                (corner & 1) as f64,
+                (corner >> 1 & 1) as f64,
+                (corner >> 2 & 1) as f64,
+            );
+            let lattice = cell + corner;
+            let index = self.permutations[0][wrap(lattice.x)]
+                ^ self.permutations[1][wrap(lattice.y)]
+                ^ self.permutations[2][wrap(lattice.z)];
+
+            let influence = corner * weight
+                + (DVec3::ONE - corner) * (DVec3::ONE - weight);
+            sum += influence.x
+                * influence.y
+                * influence.z
+                * self.gradients[index].dot(offset - corner);
+        }
+        sum
+    }
+
+    /// Sum of `depth` octaves of the absolute noise, each at
+    /// twice the frequency and half the weight of the last.
+    /// Gives the creased look of marble veins and flames.
+    pub fn turbulence(&self, point: DVec3, depth: u32) -> f64 {
+        let mut sum = 0.;
+        let mut point = point;
+        let mut weight = 1.;
+        for _ in 0..depth {
+            sum += weight * self.noise(point).abs();
+            weight *= 0.5;
+            point *= 2.;
+        }
+        sum
+    }
+
+    /// Fractal Brownian motion: `octaves` layers of signed
+    /// noise, each at twice the frequency and half the
+    /// amplitude of the last. Soft and billowy, and stays
+    /// within about ±0.5.
+    pub fn fbm(&self, point: DVec3, octaves: u32) -> f64 {
+        let mut sum = 0.;
+        let mut point = point;
+        let mut amplitude = 0.5;
+        for _ in 0..octaves {
+            sum += amplitude * self.noise(point);
+            amplitude *= 0.5;
+            point *= 2.;
+        }
+        sum
+    }
+}
+
+// The tables would drown out everything else
+impl fmt::Debug for Perlin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Perlin").finish_non_exhaustive()
+    }
+}
+
+/// Lattice coordinate to a table index
+fn wrap(coordinate: f64) -> usize {
+    (coordinate as i64).rem_euclid(POINT_COUNT as i64) as usize
+}
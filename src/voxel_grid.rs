@@ -0,0 +1,163 @@
+use std::{fmt, fs, io, path::Path};
+
+use glam::DVec3;
+
+use crate::{aabb::Aabb, image::invalid_data};
+
+/// Size of a Mitsuba `.vol` header in bytes
+const VOL_HEADER: usize = 48;
+
+/// Dense 3D grid of scalar values, such as the density or
+/// temperature of a smoke simulation, spanning a box in space.
+pub struct VoxelGrid {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    /// Box the grid fills; voxels are sampled at their centers
+    pub bounds: Aabb,
+    /// x varies fastest, then y, then z
+    pub values: Vec<f32>,
+}
+
+impl VoxelGrid {
+    /// Reads a Mitsuba `.vol` grid: the bytes `VOL` and version
+    /// 3; then little-endian `i32` encoding (1 for `f32`, 3 for
+    /// `u8` mapped to `[0, 1]`), width, height, depth and
+    /// channel count (must be 1); six `f32` for the minimum and
+    /// maximum corners of the box; and finally the values,
+    /// x fastest. Most simulation tools can export it.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<VoxelGrid> {
+        let bytes = fs::read(path)?;
+        if bytes.len() < VOL_HEADER || !bytes.starts_with(b"VOL\x03") {
+            return Err(invalid_data("not a version 3 VOL file"));
+        }
+        let int = |offset: usize| {
+            i32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+        let float = |offset: usize| {
+            f32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ]) as f64
+        };
+
+        let encoding = int(4);
+        let size = |offset| {
+            usize::try_from(int(offset))
+                .map_err(|_| invalid_data("invalid VOL resolution"))
+        };
+        let (width, height, depth) = (size(8)?, size(12)?, size(16)?);
+        if int(20) != 1 {
+            return Err(invalid_data("only single channel VOL grids"));
+        }
+        let (min, max) = (
+            DVec3::new(float(24), float(28), float(32)),
+            DVec3::new(float(36), float(40), float(44)),
+        );
+        // `sample` divides by the extent
+        if !(max - min).cmpgt(DVec3::ZERO).all() {
+            return Err(invalid_data("VOL bounding box is empty"));
+        }
+        let bounds = Aabb::new(min, max);
+
+        let count = width
+            .checked_mul(height)
+            .and_then(|count| count.checked_mul(depth))
+            .ok_or_else(|| invalid_data("VOL resolution too large"))?;
+        let data = &bytes[VOL_HEADER..];
+        let values: Vec<f32> = match encoding {
+            1 if data.len() / 4 >= count => data
+                .chunks_exact(4)
+                .take(count)
+                .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect(),
+            3 if data.len() >= count => data[..count]
+                .iter()
+                .map(|&value| value as f32 / 255.)
+                .collect(),
+            1 | 3 => return Err(invalid_data("VOL data is truncated")),
+            _ => return Err(invalid_data("unsupported VOL encoding")),
+        };
+
+        Ok(VoxelGrid {
+            width,
+            height,
+            depth,
+            bounds,
+            values,
+        })
+    }
+
+    /// Value of the voxel at `x`, `y`, `z`, clamped to the
+    /// edges.
+    pub fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        let x = x.min(self.width.saturating_sub(1));
+        let y = y.min(self.height.saturating_sub(1));
+        let z = z.min(self.depth.saturating_sub(1));
+        self.values
+            .get((z * self.height + y) * self.width + x)
+            .map_or(0., |&value| value as f64)
+    }
+
+    /// Trilinearly filtered value at `point`; zero outside
+    /// `bounds`.
+    pub fn sample(&self, point: DVec3) -> f64 {
+        let extent = self.bounds.max - self.bounds.min;
+        let local = (point - self.bounds.min) / extent;
+        if local.cmplt(DVec3::ZERO).any()
+            || local.cmpgt(DVec3::ONE).any()
+        {
+            return 0.;
+        }
+
+        // continuous voxel coordinates, voxel centers at +0.5
+        let resolution = DVec3::new(
+            self.width as f64,
+            self.height as f64,
+            self.depth as f64,
+        );
+        let p = local * resolution - 0.5;
+        let p0 = p.floor().max(DVec3::ZERO);
+        let t = (p - p0).clamp(DVec3::ZERO, DVec3::ONE);
+        let (x, y, z) = (p0.x as usize, p0.y as usize, p0.z as usize);
+
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        let plane = |z| {
+            let front =
+                lerp(self.voxel(x, y, z), self.voxel(x + 1, y, z), t.x);
+            let back = lerp(
+                self.voxel(x, y + 1, z),
+                self.voxel(x + 1, y + 1, z),
+                t.x,
+            );
+            lerp(front, back, t.y)
+        };
+        lerp(plane(z), plane(z + 1), t.z)
+    }
+
+    /// Largest value in the grid, which no filtered sample
+    /// can exceed.
+    pub fn max_value(&self) -> f64 {
+        self.values
+            .iter()
+            .fold(0., |max, &value| max.max(value as f64))
+    }
+}
+
+impl fmt::Debug for VoxelGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VoxelGrid")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("depth", &self.depth)
+            .field("bounds", &self.bounds)
+            .finish_non_exhaustive()
+    }
+}